@@ -0,0 +1,217 @@
+use std::cmp::Ordering;
+use std::io::{Read, Seek, Result, Error, ErrorKind};
+use parquet::*;
+use schema::{ColumnDescriptor, leaf_columns, find_column};
+use value::{Value, SortOrder, sort_order, comparable};
+use reader::read_column_chunk;
+
+/// Simple aggregates of a column over selected row groups
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregates {
+    /// Rows in selected row groups, i.e. COUNT(*)
+    pub num_rows: i64,
+    /// Non-null values of the column
+    pub count: i64,
+    pub null_count: i64,
+    /// None if there are no values or column's sort order is undefined (INT96, INTERVAL).
+    /// FLOAT16 values are widened to `Value::Float`.
+    pub min: Option<Value>,
+    pub max: Option<Value>,
+    /// Row groups which were read because their statistics are missing or unreliable
+    pub scanned_row_groups: Vec<usize>,
+}
+
+/// Aggregates of a single column chunk
+struct Partial {
+    count: i64,
+    null_count: i64,
+    min: Option<Value>,
+    max: Option<Value>,
+}
+
+/// Answer COUNT, NULL COUNT, MIN and MAX for the column at dot-separated `column_path` over row groups
+/// accepted by `predicate`, using row group metadata and statistics. Column chunks without
+/// usable statistics are read and scanned.
+pub fn aggregate<R, P>(file: &mut R, meta: &FileMetaData, column_path: &str, predicate: P) -> Result<Aggregates>
+    where R: Read + Seek, P: Fn(&RowGroup) -> bool
+{
+    let columns = leaf_columns(&meta.schema).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+    let column_idx = find_column(&columns, column_path).
+        ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Column not found: {}", column_path)))?;
+    let column = &columns[column_idx];
+    let element = &meta.schema[column.schema_idx];
+    let order = sort_order(element);
+    // min_value and max_value are only defined for columns ordered by their type
+    let type_order = matches!(meta.column_orders.as_ref().and_then(|orders| orders.get(column_idx)),
+        Some(&ColumnOrder::TYPEORDER(_)));
+
+    let mut res = Aggregates {
+        num_rows: 0,
+        count: 0,
+        null_count: 0,
+        min: None,
+        max: None,
+        scanned_row_groups: vec![],
+    };
+
+    for (rg_idx, row_group) in meta.row_groups.iter().enumerate() {
+        if !predicate(row_group) { continue }
+
+        let chunk = row_group.columns.get(column_idx).
+            ok_or_else(|| Error::new(ErrorKind::InvalidData, format!("Row group {} does not have column {}", rg_idx, column_idx)))?;
+
+        let partial = match from_statistics(chunk, column, element, order, type_order) {
+            Some(partial) => partial,
+            None => {
+                res.scanned_row_groups.push(rg_idx);
+                scan(file, chunk, column, element, order)?
+            }
+        };
+
+        res.num_rows += row_group.num_rows;
+        res.count += partial.count;
+        res.null_count += partial.null_count;
+        res.min = pick(res.min.take(), partial.min, order, Ordering::Less);
+        res.max = pick(res.max.take(), partial.max, order, Ordering::Greater);
+    }
+
+    Ok(res)
+}
+
+/// Aggregates from chunk statistics, None if they are missing or can not be trusted.
+/// `min_value` and `max_value` are used only if the file declares `type_order` for the column.
+fn from_statistics(chunk: &ColumnChunk, column: &ColumnDescriptor, element: &SchemaElement, order: SortOrder,
+    type_order: bool) -> Option<Partial>
+{
+    let meta = chunk.meta_data.as_ref()?;
+    let stats = meta.statistics.as_ref()?;
+
+    let null_count = if column.max_def_level == 0 { 0 } else { stats.null_count? };
+    let count = meta.num_values - null_count;
+    if count < 0 { return None }
+
+    if count == 0 || order == SortOrder::Undefined {
+        return Some(Partial { count, null_count, min: None, max: None })
+    }
+
+    // Deprecated min/max were computed with signed comparison and are fine only for
+    // numeric types, where it matches the column order.
+    let deprecated_ok = order == SortOrder::Signed &&
+        !matches!(column.type_, Type::BYTE_ARRAY | Type::FIXED_LEN_BYTE_ARRAY | Type::INT96);
+    let (min, max) = match (&stats.min_value, &stats.max_value, &stats.min, &stats.max) {
+        (Some(min), Some(max), _, _) if type_order => (min, max),
+        (_, _, Some(min), Some(max)) if deprecated_ok => (min, max),
+        _ => return None,
+    };
+
+    let min = comparable(element, Value::from_plain(column.type_, min).ok()?).ok()?;
+    let max = comparable(element, Value::from_plain(column.type_, max).ok()?).ok()?;
+    // NaN bounds do not tell anything about other values
+    min.compare(&max, order)?;
+
+    Some(Partial { count, null_count, min: Some(min), max: Some(max) })
+}
+
+fn scan<R: Read + Seek>(file: &mut R, chunk: &ColumnChunk, column: &ColumnDescriptor, element: &SchemaElement,
+    order: SortOrder) -> Result<Partial>
+{
+    let data = read_column_chunk(file, chunk, column)?;
+    let null_count = data.def_levels.iter().filter(|&&d| d < column.max_def_level).count() as i64;
+
+    let mut min = None;
+    let mut max = None;
+    if order != SortOrder::Undefined {
+        for value in data.values.iter() {
+            let value = comparable(element, value.clone()).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+            // skip NaN
+            if value.compare(&value, order).is_none() { continue }
+            min = pick(min, Some(value.clone()), order, Ordering::Less);
+            max = pick(max, Some(value), order, Ordering::Greater);
+        }
+    }
+
+    Ok(Partial { count: data.values.len() as i64, null_count, min, max })
+}
+
+/// Choose `b` over `a` if it compares as `wanted`
+fn pick(a: Option<Value>, b: Option<Value>, order: SortOrder, wanted: Ordering) -> Option<Value> {
+    match (a, b) {
+        (Some(a), Some(b)) => if b.compare(&a, order) == Some(wanted) { Some(b) } else { Some(a) },
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use reader::tests::{optional_int64_page, chunk};
+
+    fn schema() -> Vec<SchemaElement> {
+        vec![
+            SchemaElement::new(None, None, None, "schema".to_string(), 1, None, None, None, None, None),
+            SchemaElement::new(Type::INT64, None, FieldRepetitionType::OPTIONAL, "v".to_string(), None, None, None, None, None, None),
+        ]
+    }
+
+    fn stats(min: i64, max: i64, null_count: i64) -> Statistics {
+        Statistics::new(None, None, null_count, None, Value::Int64(max).to_plain(), Value::Int64(min).to_plain())
+    }
+
+    #[test]
+    fn metadata_only() {
+        let mut rg1 = chunk(4, 0, 10);
+        rg1.meta_data.as_mut().unwrap().statistics = Some(stats(-5, 7, 2));
+        let mut rg2 = chunk(4, 0, 20);
+        rg2.meta_data.as_mut().unwrap().statistics = Some(stats(3, 100, 0));
+        let mut meta = FileMetaData::new(1, schema(), 30, vec![
+            RowGroup::new(vec![rg1], 0, 10, None),
+            RowGroup::new(vec![rg2], 0, 20, None),
+        ], None, None, vec![ColumnOrder::TYPEORDER(TypeDefinedOrder::new())]);
+
+        let res = aggregate(&mut Cursor::new(vec![]), &meta, "v", |_| true).unwrap();
+        assert_eq!((30, 28, 2), (res.num_rows, res.count, res.null_count));
+        assert_eq!(Some(Value::Int64(-5)), res.min);
+        assert_eq!(Some(Value::Int64(100)), res.max);
+        assert!(res.scanned_row_groups.is_empty());
+
+        let res = aggregate(&mut Cursor::new(vec![]), &meta, "v", |rg| rg.num_rows > 10).unwrap();
+        assert_eq!((20, 20, 0), (res.num_rows, res.count, res.null_count));
+        assert_eq!(Some(Value::Int64(3)), res.min);
+
+        // without column orders min_value and max_value are ignored, deprecated min and max of numbers are not
+        meta.column_orders = None;
+        let res = aggregate(&mut Cursor::new(vec![]), &meta, "v", |_| true).unwrap();
+        assert_eq!(vec![0, 1], res.scanned_row_groups);
+        {
+            let stats = meta.row_groups[0].columns[0].meta_data.as_mut().unwrap().statistics.as_mut().unwrap();
+            stats.min = stats.min_value.clone();
+            stats.max = stats.max_value.clone();
+        }
+        let res = aggregate(&mut Cursor::new(vec![]), &meta, "v", |_| true).unwrap();
+        assert_eq!(vec![1], res.scanned_row_groups);
+        assert_eq!((Some(Value::Int64(-5)), Some(Value::Int64(7))), (res.min, res.max));
+    }
+
+    #[test]
+    fn scan_without_statistics() {
+        let mut file = b"PAR1".to_vec();
+        let page = optional_int64_page(&[Some(8), None, Some(-2), Some(4)]);
+        let len = page.len();
+        file.extend(page);
+
+        let mut with_stats = chunk(4, len, 4);
+        with_stats.meta_data.as_mut().unwrap().statistics = Some(stats(0, 1, 0));
+        let meta = FileMetaData::new(1, schema(), 8, vec![
+            RowGroup::new(vec![chunk(4, len, 4)], 0, 4, None),
+            RowGroup::new(vec![with_stats], 0, 4, None),
+        ], None, None, vec![ColumnOrder::TYPEORDER(TypeDefinedOrder::new())]);
+
+        let res = aggregate(&mut Cursor::new(file), &meta, "v", |_| true).unwrap();
+        assert_eq!(vec![0], res.scanned_row_groups);
+        assert_eq!((8, 7, 1), (res.num_rows, res.count, res.null_count));
+        assert_eq!(Some(Value::Int64(-2)), res.min);
+        assert_eq!(Some(Value::Int64(8)), res.max);
+    }
+}
@@ -0,0 +1,1152 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
+use std::io::{Result, Error, ErrorKind};
+use std::marker::PhantomData;
+use byteorder::{LittleEndian, WriteBytesExt};
+use thrift::protocol::{TCompactOutputProtocol, TOutputProtocol};
+use parquet::*;
+use schema::ColumnDescriptor;
+use value::{Value, SortOrder, sort_order, comparable, is_float16};
+use encodings::{encode_plain, encode_values, encode_rle, can_encode, bit_width};
+use index::{truncate_min, truncate_max};
+use compression::{Codec, create_codec, compress_if_smaller};
+
+/// Page size the writer aims for when none is configured
+pub const DEFAULT_PAGE_SIZE: usize = 1024 * 1024;
+/// Length of byte array min/max values in page indexes when none is configured
+pub const DEFAULT_TRUNCATE_LENGTH: usize = 64;
+
+fn invalid_input<E: ToString>(e: E) -> Error {
+    Error::new(ErrorKind::InvalidInput, e.to_string())
+}
+
+/// Native values a column writer accepts, each maps to one or more physical types
+pub trait PhysicalValue: Clone {
+    fn accepts(type_: Type) -> bool;
+    fn to_value(&self) -> Value;
+}
+
+impl PhysicalValue for bool {
+    fn accepts(type_: Type) -> bool { type_ == Type::BOOLEAN }
+    fn to_value(&self) -> Value { Value::Boolean(*self) }
+}
+
+impl PhysicalValue for i32 {
+    fn accepts(type_: Type) -> bool { type_ == Type::INT32 }
+    fn to_value(&self) -> Value { Value::Int32(*self) }
+}
+
+impl PhysicalValue for i64 {
+    fn accepts(type_: Type) -> bool { type_ == Type::INT64 }
+    fn to_value(&self) -> Value { Value::Int64(*self) }
+}
+
+impl PhysicalValue for [u32; 3] {
+    fn accepts(type_: Type) -> bool { type_ == Type::INT96 }
+    fn to_value(&self) -> Value { Value::Int96(*self) }
+}
+
+impl PhysicalValue for f32 {
+    fn accepts(type_: Type) -> bool { type_ == Type::FLOAT }
+    fn to_value(&self) -> Value { Value::Float(*self) }
+}
+
+impl PhysicalValue for f64 {
+    fn accepts(type_: Type) -> bool { type_ == Type::DOUBLE }
+    fn to_value(&self) -> Value { Value::Double(*self) }
+}
+
+/// BYTE_ARRAY and FIXED_LEN_BYTE_ARRAY
+impl PhysicalValue for Vec<u8> {
+    fn accepts(type_: Type) -> bool { type_ == Type::BYTE_ARRAY || type_ == Type::FIXED_LEN_BYTE_ARRAY }
+    fn to_value(&self) -> Value { Value::Bytes(self.clone()) }
+}
+
+/// Any type, checked against the column when values are written
+impl PhysicalValue for Value {
+    fn accepts(_: Type) -> bool { true }
+    fn to_value(&self) -> Value { self.clone() }
+}
+
+/// Encoded pages of a column chunk, not yet placed in a file.
+/// Offsets in `meta` are relative to the start of `data`.
+#[derive(Clone, Debug)]
+pub struct ChunkBuffer {
+    pub data: Vec<u8>,
+    meta: ColumnMetaData,
+    num_rows: i64,
+    column_index: Option<ColumnIndex>,
+    offset_index: OffsetIndex,
+}
+
+impl ChunkBuffer {
+    pub fn num_rows(&self) -> i64 {
+        self.num_rows
+    }
+
+    pub fn meta_data(&self) -> &ColumnMetaData {
+        &self.meta
+    }
+
+    /// Column chunk describing `data` written at `offset` of the file
+    pub fn column_chunk(&self, offset: i64) -> ColumnChunk {
+        let mut meta = self.meta.clone();
+        meta.data_page_offset += offset;
+        meta.dictionary_page_offset = meta.dictionary_page_offset.map(|o| o + offset);
+        ColumnChunk::new(None, offset, meta, None, None, None, None)
+    }
+
+    /// Page min/max values, None if statistics are disabled or the column has no usable bounds
+    pub fn column_index(&self) -> Option<&ColumnIndex> {
+        self.column_index.as_ref()
+    }
+
+    /// Locations of data pages of `data` written at `offset` of the file
+    pub fn offset_index(&self, offset: i64) -> OffsetIndex {
+        let mut index = self.offset_index.clone();
+        for location in index.page_locations.iter_mut() {
+            location.offset += offset;
+        }
+        index
+    }
+}
+
+/// Version of data pages: DATA_PAGE of format 1.0 or DATA_PAGE_V2 of format 2.0
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriterVersion {
+    V1,
+    V2,
+}
+
+/// Options of a column writer
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnProperties {
+    /// Page size the writer aims for, pages end on row boundaries and may be larger
+    pub page_size: usize,
+    /// Encode values with a dictionary while it stays within `dictionary_page_size_limit` bytes
+    pub dictionary_enabled: bool,
+    pub dictionary_page_size_limit: usize,
+    /// Encoding of values without dictionary, including those after the dictionary grew too large:
+    /// PLAIN, RLE for booleans of V2 pages, DELTA_BINARY_PACKED for integers or DELTA_*_BYTE_ARRAY for byte arrays
+    pub encoding: Encoding,
+    /// Write min, max and null count of every page and of the chunk, and the page `ColumnIndex`
+    pub statistics_enabled: bool,
+    /// Byte array min/max values in `ColumnIndex` are truncated to this length
+    pub column_index_truncate_length: Option<usize>,
+    /// Codec of all pages, one that has an implementation in `compression`
+    pub compression: CompressionCodec,
+    /// DATA_PAGE_V2 pages keep levels uncompressed and store values uncompressed
+    /// when compression does not make them smaller
+    pub writer_version: WriterVersion,
+}
+
+impl Default for ColumnProperties {
+    fn default() -> ColumnProperties {
+        ColumnProperties {
+            page_size: DEFAULT_PAGE_SIZE,
+            dictionary_enabled: true,
+            dictionary_page_size_limit: DEFAULT_PAGE_SIZE,
+            encoding: Encoding::PLAIN,
+            statistics_enabled: true,
+            column_index_truncate_length: Some(DEFAULT_TRUNCATE_LENGTH),
+            compression: CompressionCodec::UNCOMPRESSED,
+            writer_version: WriterVersion::V1,
+        }
+    }
+}
+
+/// Dictionary of a column chunk, keyed by PLAIN encoded value
+struct Dictionary {
+    indices: HashMap<Vec<u8>, u32>,
+    values: Vec<Value>,
+    /// Size of the dictionary page
+    size: usize,
+    /// Reached size limit, values are not added any more
+    full: bool,
+}
+
+/// Min and max of values in column order, and null count
+#[derive(Clone)]
+struct StatisticsCollector {
+    order: SortOrder,
+    element: SchemaElement,
+    min: Option<Value>,
+    max: Option<Value>,
+    num_values: i64,
+    null_count: i64,
+}
+
+impl StatisticsCollector {
+    fn new(element: &SchemaElement) -> StatisticsCollector {
+        StatisticsCollector { order: sort_order(element), element: element.clone(), min: None, max: None, num_values: 0, null_count: 0 }
+    }
+
+    fn update(&mut self, value: &Value) {
+        self.num_values += 1;
+        self.update_bounds(value);
+    }
+
+    fn update_bounds(&mut self, value: &Value) {
+        // NaN does not order with anything, not even itself, and is left out
+        if self.compare(value, value).is_none() { return }
+        if self.min.as_ref().map_or(true, |min| self.compare(value, min) == Some(Ordering::Less)) {
+            self.min = Some(value.clone());
+        }
+        if self.max.as_ref().map_or(true, |max| self.compare(value, max) == Some(Ordering::Greater)) {
+            self.max = Some(value.clone());
+        }
+    }
+
+    fn merge(&mut self, other: &StatisticsCollector) {
+        for value in other.min.iter().chain(other.max.iter()) {
+            self.update_bounds(value);
+        }
+        self.num_values += other.num_values;
+        self.null_count += other.null_count;
+    }
+
+    fn compare(&self, a: &Value, b: &Value) -> Option<Ordering> {
+        if is_float16(&self.element) {
+            let a = comparable(&self.element, a.clone()).ok()?;
+            let b = comparable(&self.element, b.clone()).ok()?;
+            return a.compare(&b, self.order)
+        }
+        a.compare(b, self.order)
+    }
+
+    /// Encoded min and max. Zeros compare equal, so bounds are zeros of the sign that includes both.
+    fn bounds(&self) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+        (self.min.clone().map(|v| self.signed_zero(v, true).to_plain()),
+         self.max.clone().map(|v| self.signed_zero(v, false).to_plain()))
+    }
+
+    fn to_thrift(&self, distinct_count: Option<i64>) -> Statistics {
+        let (min, max) = self.bounds();
+        // deprecated fields are compared as signed, which is the column order of numeric types only
+        let deprecated = self.order == SortOrder::Signed &&
+            matches!(self.element.type_, Some(Type::INT32) | Some(Type::INT64) | Some(Type::FLOAT) | Some(Type::DOUBLE));
+        let (old_max, old_min) = if deprecated { (max.clone(), min.clone()) } else { (None, None) };
+        Statistics::new(old_max, old_min, self.null_count, distinct_count, max, min)
+    }
+
+    /// Zero of the given sign if `value` is a floating point zero: -0.0 for min and +0.0 for max
+    fn signed_zero(&self, value: Value, negative: bool) -> Value {
+        match value {
+            Value::Float(0.0) => Value::Float(if negative { -0.0 } else { 0.0 }),
+            Value::Double(0.0) => Value::Double(if negative { -0.0 } else { 0.0 }),
+            // FLOAT16 zero, little endian
+            Value::Bytes(ref v) if is_float16(&self.element) && v[0] == 0 && v[1] & 0x7f == 0 =>
+                Value::Bytes(vec![0, if negative { 0x80 } else { 0 }]),
+            value => value,
+        }
+    }
+}
+
+/// Writes values of one leaf column into pages of a column chunk.
+/// Values are buffered until the page reaches `page_size` bytes, pages end on row boundaries.
+/// Values are dictionary encoded until the dictionary exceeds its size limit,
+/// the following pages use the fallback encoding.
+/// Page and chunk statistics compare values in the order of the column's logical type.
+pub struct ColumnWriter<T: PhysicalValue> {
+    column: ColumnDescriptor,
+    props: ColumnProperties,
+    codec: Option<Box<dyn Codec>>,
+    page_stats: StatisticsCollector,
+    chunk_stats: StatisticsCollector,
+    /// Statistics and locations of finished data pages, offsets relative to `data`
+    pages: Vec<StatisticsCollector>,
+    page_locations: Vec<PageLocation>,
+    /// Index of the first row of the buffered page in the chunk
+    page_first_row: i64,
+    /// Present while values are dictionary encoded
+    dictionary: Option<Dictionary>,
+    /// Whether any page has been dictionary encoded
+    dictionary_used: bool,
+    /// Encoded dictionary page and its size uncompressed, once `finish` encoded it
+    dictionary_page: Option<(Vec<u8>, usize)>,
+    /// Buffered page: either plain values or dictionary indices
+    values: Vec<Value>,
+    indices: Vec<u32>,
+    def_levels: Vec<i16>,
+    rep_levels: Vec<i16>,
+    /// Estimated encoded size of the buffered plain values
+    page_bytes: usize,
+    /// Finished data pages
+    data: Vec<u8>,
+    /// Size of `data` with pages uncompressed
+    uncompressed_size: usize,
+    encoding_stats: Vec<PageEncodingStats>,
+    num_values: i64,
+    num_rows: i64,
+    _type: PhantomData<T>,
+}
+
+impl<T: PhysicalValue> ColumnWriter<T> {
+    /// Writer of `column`, whose schema element is `element`
+    pub fn new(column: ColumnDescriptor, element: &SchemaElement, props: ColumnProperties) -> Result<ColumnWriter<T>> {
+        if !T::accepts(column.type_) {
+            return Err(invalid_input(format!("Column '{}' of type {:?} can not be written with this writer",
+                column.path_string(), column.type_)))
+        }
+        if !can_encode(props.encoding, column.type_) {
+            return Err(invalid_input(format!("Column '{}' of type {:?} can not be written with {:?} encoding",
+                column.path_string(), column.type_, props.encoding)))
+        }
+        // readers only take RLE encoded values in DATA_PAGE_V2
+        if props.encoding == Encoding::RLE && props.writer_version != WriterVersion::V2 {
+            return Err(invalid_input(format!("Column '{}' can only be written with RLE encoding in V2 pages",
+                column.path_string())))
+        }
+
+        let codec = match props.compression {
+            CompressionCodec::UNCOMPRESSED => None,
+            codec => Some(create_codec(codec).ok_or_else(|| invalid_input(format!(
+                "Column '{}' can not be compressed with {:?}, it is not implemented yet", column.path_string(), codec)))?),
+        };
+
+        // booleans take a bit each, a dictionary does not make them smaller
+        let dictionary = if props.dictionary_enabled && column.type_ != Type::BOOLEAN {
+            Some(Dictionary { indices: HashMap::new(), values: vec![], size: 0, full: false })
+        } else {
+            None
+        };
+        let stats = StatisticsCollector::new(element);
+        Ok(ColumnWriter {
+            column,
+            props,
+            codec,
+            page_stats: stats.clone(),
+            chunk_stats: stats,
+            pages: vec![],
+            page_locations: vec![],
+            page_first_row: 0,
+            dictionary,
+            dictionary_used: false,
+            dictionary_page: None,
+            values: vec![],
+            indices: vec![],
+            def_levels: vec![],
+            rep_levels: vec![],
+            page_bytes: 0,
+            data: vec![],
+            uncompressed_size: 0,
+            encoding_stats: vec![],
+            num_values: 0,
+            num_rows: 0,
+            _type: PhantomData,
+        })
+    }
+
+    pub fn column(&self) -> &ColumnDescriptor {
+        &self.column
+    }
+
+    /// Level entries written so far, including nulls and empty lists
+    pub fn num_values(&self) -> i64 {
+        self.num_values
+    }
+
+    pub fn num_rows(&self) -> i64 {
+        self.num_rows
+    }
+
+    /// Estimated size of the chunk if it was closed now: finished pages, the buffered page and the dictionary page
+    pub fn estimated_size(&self) -> usize {
+        let dictionary = match self.dictionary {
+            Some(ref dictionary) if self.dictionary_used || !dictionary.full => dictionary.size,
+            _ => 0,
+        };
+        self.data.len() + self.page_size() + dictionary
+    }
+
+    /// Approximate bytes of memory held: finished pages, buffered values and levels, and the dictionary
+    pub fn memory_size(&self) -> usize {
+        let value_size = mem::size_of::<Value>();
+        let dictionary = self.dictionary.as_ref().map_or(0, |d| {
+            // values, and their PLAIN encoded keys in the index
+            d.values.capacity() * value_size + d.indices.capacity() * mem::size_of::<(Vec<u8>, u32)>() + d.size * 2
+        });
+        self.data.capacity() + self.values.capacity() * value_size + self.page_bytes + self.indices.capacity() * 4 +
+            (self.def_levels.capacity() + self.rep_levels.capacity()) * 2 + dictionary +
+            self.dictionary_page.as_ref().map_or(0, |page| page.0.len())
+    }
+
+    /// Check a batch as `write_batch` does, without writing it
+    pub fn check_batch(&self, values: &[T], def_levels: Option<&[i16]>, rep_levels: Option<&[i16]>) -> Result<()> {
+        self.validate(values, def_levels, rep_levels).map(|_| ())
+    }
+
+    /// Write a batch of non-null `values` with their levels. Levels are required when the column
+    /// has such levels, and have an entry per value slot, including nulls.
+    /// The batch is validated before anything is buffered, an invalid one is rejected as a whole.
+    /// Returns number of values written.
+    pub fn write_batch(&mut self, values: &[T], def_levels: Option<&[i16]>, rep_levels: Option<&[i16]>) -> Result<usize> {
+        let (def_levels, rep_levels, batch) = self.validate(values, def_levels, rep_levels)?;
+        let mut batch = batch.into_iter();
+        for i in 0..def_levels.len() {
+            if rep_levels[i] == 0 && !self.def_levels.is_empty() && self.page_size() >= self.props.page_size {
+                self.flush_page()?;
+            }
+            if self.def_levels.is_empty() {
+                self.page_first_row = self.num_rows;
+            }
+            if rep_levels[i] == 0 {
+                self.num_rows += 1;
+            }
+            if def_levels[i] == self.column.max_def_level {
+                let value = batch.next().unwrap();
+                if self.props.statistics_enabled {
+                    self.page_stats.update(&value);
+                }
+                self.push_value(value)?;
+            } else {
+                self.page_stats.null_count += 1;
+            }
+            self.def_levels.push(def_levels[i]);
+            self.rep_levels.push(rep_levels[i]);
+        }
+        Ok(values.len())
+    }
+
+    /// Levels of every entry of a batch and its values
+    fn validate(&self, values: &[T], def_levels: Option<&[i16]>, rep_levels: Option<&[i16]>) -> Result<(Vec<i16>, Vec<i16>, Vec<Value>)> {
+        let path = self.column.path_string();
+        let levels = |levels: Option<&[i16]>, max_level: i16, kind: &str| -> Result<Option<Vec<i16>>> {
+            match levels {
+                None if max_level > 0 => Err(invalid_input(format!("Column '{}' needs {} levels", path, kind))),
+                None => Ok(None),
+                Some(levels) => match levels.iter().find(|&&l| l < 0 || l > max_level) {
+                    Some(l) => Err(invalid_input(format!("Column '{}' {} level {} is out of 0..{}", path, kind, l, max_level))),
+                    None => Ok(Some(levels.to_vec())),
+                },
+            }
+        };
+        let def_levels = levels(def_levels, self.column.max_def_level, "definition")?;
+        let rep_levels = levels(rep_levels, self.column.max_rep_level, "repetition")?;
+
+        let num_entries = def_levels.as_ref().map_or(values.len(), |l| l.len());
+        let def_levels = def_levels.unwrap_or_else(|| vec![self.column.max_def_level; num_entries]);
+        let rep_levels = rep_levels.unwrap_or_else(|| vec![0; num_entries]);
+        if rep_levels.len() != num_entries {
+            return Err(invalid_input(format!("Column '{}' has {} definition but {} repetition levels",
+                path, num_entries, rep_levels.len())))
+        }
+        let non_null = def_levels.iter().filter(|&&d| d == self.column.max_def_level).count();
+        if non_null != values.len() {
+            return Err(invalid_input(format!("Column '{}' levels define {} values but got {}", path, non_null, values.len())))
+        }
+        if self.num_values == 0 && self.def_levels.is_empty() && rep_levels.first().is_some_and(|&r| r != 0) {
+            return Err(invalid_input(format!("Column '{}' must start with a new row", path)))
+        }
+        let type_length = self.column.type_length.unwrap_or(0);
+        let batch: Vec<Value> = values.iter().map(|v| v.to_value()).collect();
+        if let Some(value) = batch.iter().find(|v| !v.matches(self.column.type_, type_length)) {
+            return Err(invalid_input(format!("Column '{}' of type {:?} (length {}) can not hold {:?}",
+                path, self.column.type_, type_length, value)))
+        }
+        Ok((def_levels, rep_levels, batch))
+    }
+
+    /// Flush the buffered page and encode the dictionary page. If this fails the writer keeps
+    /// its data, and can still take values or be closed.
+    pub fn finish(&mut self) -> Result<()> {
+        if !self.def_levels.is_empty() {
+            self.flush_page()?;
+        }
+        if self.dictionary_page.is_some() || !self.dictionary_used {
+            return Ok(())
+        }
+
+        let mut body = vec![];
+        let num_values = match self.dictionary {
+            Some(ref dictionary) => {
+                encode_plain(self.column.type_, self.column.type_length.unwrap_or(0), &dictionary.values, &mut body).
+                    map_err(|e| invalid_input(format!("Column '{}': {}", self.column.path_string(), e)))?;
+                dictionary.values.len()
+            },
+            None => return Ok(()),
+        };
+        let uncompressed_len = body.len();
+        let body = self.compress(body)?;
+        let dict_header = DictionaryPageHeader::new(num_values as i32, Encoding::PLAIN, None);
+        let header = PageHeader::new(PageType::DICTIONARY_PAGE, uncompressed_len as i32, body.len() as i32, None,
+            None, None, dict_header, None);
+        let mut page = vec![];
+        write_page(&mut page, &header, &body)?;
+        let uncompressed_size = page.len() - body.len() + uncompressed_len;
+        self.dictionary_page = Some((page, uncompressed_size));
+        Ok(())
+    }
+
+    /// Flush the last page and return encoded chunk: dictionary page, if any, followed by data pages
+    pub fn close(mut self) -> Result<ChunkBuffer> {
+        self.finish()?;
+
+        let mut data = vec![];
+        let mut encodings = vec![];
+        let mut dictionary_page_offset = None;
+        let mut dictionary_len = None;
+        let fallback_used = self.encoding_stats.iter().any(|s| s.encoding != Encoding::RLE_DICTIONARY);
+        if let Some((page, uncompressed_size)) = self.dictionary_page.take() {
+            data = page;
+            self.uncompressed_size += uncompressed_size;
+            dictionary_page_offset = Some(0);
+            dictionary_len = self.dictionary.as_ref().map(|d| d.values.len());
+            self.encoding_stats.insert(0, PageEncodingStats::new(PageType::DICTIONARY_PAGE, Encoding::PLAIN, 1));
+        }
+        let data_page_offset = data.len() as i64;
+        data.extend(mem::take(&mut self.data));
+        for location in self.page_locations.iter_mut() {
+            location.offset += data_page_offset;
+        }
+        let column_index = if self.props.statistics_enabled { self.column_index() } else { None };
+
+        for stats in self.encoding_stats.iter() {
+            if !encodings.contains(&stats.encoding) { encodings.push(stats.encoding) }
+        }
+        if (self.column.max_def_level > 0 || self.column.max_rep_level > 0) && !encodings.contains(&Encoding::RLE) {
+            encodings.push(Encoding::RLE);
+        }
+
+        let statistics = if self.props.statistics_enabled {
+            // a dictionary holding every value of the chunk counts them exactly, except floating point values
+            // it keys by their bits: 0.0 and -0.0, or NaNs with different payloads, are separate entries
+            let floating = self.column.type_ == Type::FLOAT || self.column.type_ == Type::DOUBLE ||
+                is_float16(&self.chunk_stats.element);
+            let distinct_count = dictionary_len.filter(|_| !fallback_used && !floating).map(|len| len as i64);
+            Some(self.chunk_stats.to_thrift(distinct_count))
+        } else {
+            None
+        };
+        let meta = ColumnMetaData::new(self.column.type_, encodings, self.column.path.clone(), self.props.compression,
+            self.num_values, self.uncompressed_size as i64, data.len() as i64, None, data_page_offset, None,
+            dictionary_page_offset, statistics, self.encoding_stats);
+        Ok(ChunkBuffer { data, meta, num_rows: self.num_rows, column_index, offset_index: OffsetIndex::new(self.page_locations) })
+    }
+
+    fn push_value(&mut self, value: Value) -> Result<()> {
+        let limit = self.props.dictionary_page_size_limit;
+        if let Some(ref mut dictionary) = self.dictionary {
+            if !dictionary.full {
+                let key = value.to_plain();
+                if let Some(&index) = dictionary.indices.get(&key) {
+                    self.indices.push(index);
+                    return Ok(())
+                }
+                if dictionary.size + value.plain_size() <= limit {
+                    // a dictionary page encoded by `finish` is out of date
+                    self.dictionary_page = None;
+                    let index = dictionary.values.len() as u32;
+                    dictionary.indices.insert(key, index);
+                    dictionary.size += value.plain_size();
+                    dictionary.values.push(value);
+                    self.indices.push(index);
+                    return Ok(())
+                }
+
+                // Dictionary is full: it keeps serving pages already written, the buffered
+                // page and the rest of the chunk use fallback encoding
+                dictionary.full = true;
+                dictionary.indices.clear();
+                for index in self.indices.drain(..) {
+                    let value = dictionary.values[index as usize].clone();
+                    self.page_bytes += value.plain_size();
+                    self.values.push(value);
+                }
+            }
+        }
+
+        self.page_bytes += value.plain_size();
+        self.values.push(value);
+        Ok(())
+    }
+
+    /// Page data compressed with the chunk's codec
+    fn compress(&mut self, body: Vec<u8>) -> Result<Vec<u8>> {
+        match self.codec {
+            Some(ref mut codec) => codec.compress(&body),
+            None => Ok(body),
+        }
+    }
+
+    /// Page min/max values, None when a page has values but no bounds: the column order
+    /// is undefined or all of them are NaN
+    fn column_index(&self) -> Option<ColumnIndex> {
+        let truncate = match self.column.type_ {
+            Type::BYTE_ARRAY | Type::FIXED_LEN_BYTE_ARRAY if self.chunk_stats.order == SortOrder::Unsigned =>
+                self.props.column_index_truncate_length,
+            _ => None,
+        };
+
+        let mut null_pages = vec![];
+        let mut min_values = vec![];
+        let mut max_values = vec![];
+        // decoded bounds of pages with values, in page order
+        let mut bounds = vec![];
+        for page in self.pages.iter() {
+            let (min, max) = match page.bounds() {
+                (Some(min), Some(max)) => match truncate {
+                    Some(len) => (truncate_min(&min, len), truncate_max(&max, len)),
+                    None => (min, max),
+                },
+                _ if page.num_values == 0 => {
+                    null_pages.push(true);
+                    min_values.push(vec![]);
+                    max_values.push(vec![]);
+                    continue
+                },
+                _ => return None,
+            };
+            bounds.push((Value::from_plain(self.column.type_, &min).ok()?, Value::from_plain(self.column.type_, &max).ok()?));
+            null_pages.push(false);
+            min_values.push(min);
+            max_values.push(max);
+        }
+
+        // bounds are ordered when both min and max values are
+        let ordered = |wanted: Ordering| bounds.windows(2).all(|pair| {
+            let in_order = |a: &Value, b: &Value| self.chunk_stats.compare(a, b).is_some_and(|o| o == wanted || o == Ordering::Equal);
+            in_order(&pair[0].0, &pair[1].0) && in_order(&pair[0].1, &pair[1].1)
+        });
+        let boundary_order = if ordered(Ordering::Less) {
+            BoundaryOrder::ASCENDING
+        } else if ordered(Ordering::Greater) {
+            BoundaryOrder::DESCENDING
+        } else {
+            BoundaryOrder::UNORDERED
+        };
+
+        let null_counts = self.pages.iter().map(|p| p.null_count).collect::<Vec<_>>();
+        Some(ColumnIndex::new(null_pages, min_values, max_values, boundary_order, null_counts))
+    }
+
+    /// Estimated encoded size of the buffered page: its values or dictionary indices, and its levels bit packed
+    fn page_size(&self) -> usize {
+        let values = match self.dictionary {
+            Some(ref dictionary) if !dictionary.full =>
+                self.indices.len() * bit_width(dictionary.values.len() as u32) as usize / 8,
+            _ => self.page_bytes,
+        };
+        let level_bits = self.def_levels.len() * bit_width(self.column.max_def_level as u32) as usize +
+            self.rep_levels.len() * bit_width(self.column.max_rep_level as u32) as usize;
+        values + level_bits.div_ceil(8)
+    }
+
+    fn flush_page(&mut self) -> Result<()> {
+        let v2 = self.props.writer_version == WriterVersion::V2;
+        // v1 pages prefix levels with their length, v2 page headers hold it
+        let mut levels_lengths = vec![];
+        let mut body = vec![];
+        for &(levels, max_level) in [(&self.rep_levels, self.column.max_rep_level), (&self.def_levels, self.column.max_def_level)].iter() {
+            if max_level == 0 {
+                levels_lengths.push(0);
+                continue
+            }
+            let levels: Vec<u32> = levels.iter().map(|&l| l as u32).collect();
+            let encoded = encode_rle(&levels, bit_width(max_level as u32));
+            levels_lengths.push(encoded.len());
+            if !v2 {
+                body.write_u32::<LittleEndian>(encoded.len() as u32)?;
+            }
+            body.extend(encoded);
+        }
+        let levels_len = if v2 { body.len() } else { 0 };
+
+        let encoding = match self.dictionary {
+            Some(ref dictionary) if !dictionary.full => {
+                let width = bit_width(dictionary.values.len().saturating_sub(1) as u32).max(1);
+                body.push(width as u8);
+                body.extend(encode_rle(&self.indices, width));
+                self.dictionary_used = true;
+                Encoding::RLE_DICTIONARY
+            },
+            _ => {
+                encode_values(self.props.encoding, self.column.type_, self.column.type_length.unwrap_or(0), &self.values, &mut body).
+                    map_err(|e| invalid_input(format!("Column '{}': {}", self.column.path_string(), e)))?;
+                self.props.encoding
+            },
+        };
+
+        let num_values = self.def_levels.len() as i32;
+        let statistics = if self.props.statistics_enabled { Some(self.page_stats.to_thrift(None)) } else { None };
+        let uncompressed_len = body.len();
+        let (page_type, header, body) = if v2 {
+            // levels stay uncompressed, values too when compression does not pay off
+            let values = body.split_off(levels_len);
+            let compressed = match self.codec {
+                Some(ref mut codec) => compress_if_smaller(&mut **codec, &values)?,
+                None => None,
+            };
+            let is_compressed = compressed.is_some();
+            body.extend(compressed.unwrap_or(values));
+
+            let num_nulls = self.def_levels.iter().filter(|&&d| d < self.column.max_def_level).count() as i32;
+            let num_rows = if self.column.max_rep_level > 0 {
+                self.rep_levels.iter().filter(|&&r| r == 0).count() as i32
+            } else {
+                num_values
+            };
+            let data_header = DataPageHeaderV2::new(num_values, num_nulls, num_rows, encoding, levels_lengths[1] as i32,
+                levels_lengths[0] as i32, is_compressed, statistics);
+            let header = PageHeader::new(PageType::DATA_PAGE_V2, uncompressed_len as i32, body.len() as i32, None,
+                None, None, None, data_header);
+            (PageType::DATA_PAGE_V2, header, body)
+        } else {
+            let data_header = DataPageHeader::new(num_values, encoding, Encoding::RLE, Encoding::RLE, statistics);
+            let body = self.compress(body)?;
+            let header = PageHeader::new(PageType::DATA_PAGE, uncompressed_len as i32, body.len() as i32, None,
+                data_header, None, None, None);
+            (PageType::DATA_PAGE, header, body)
+        };
+        let offset = self.data.len();
+        write_page(&mut self.data, &header, &body)?;
+        self.uncompressed_size += self.data.len() - offset - body.len() + uncompressed_len;
+        self.page_locations.push(PageLocation::new(offset as i64, (self.data.len() - offset) as i32, self.page_first_row));
+
+        match self.encoding_stats.iter_mut().find(|s| s.page_type == page_type && s.encoding == encoding) {
+            Some(stats) => stats.count += 1,
+            None => self.encoding_stats.push(PageEncodingStats::new(page_type, encoding, 1)),
+        }
+        self.num_values += num_values as i64;
+        let page_stats = mem::replace(&mut self.page_stats, StatisticsCollector::new(&self.chunk_stats.element));
+        self.chunk_stats.merge(&page_stats);
+        self.pages.push(page_stats);
+        self.values.clear();
+        self.indices.clear();
+        self.def_levels.clear();
+        self.rep_levels.clear();
+        self.page_bytes = 0;
+        Ok(())
+    }
+}
+
+/// Append serialized page header and page data
+pub fn write_page(out: &mut Vec<u8>, header: &PageHeader, data: &[u8]) -> Result<()> {
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut *out);
+        header.write_to_out_protocol(&mut protocol).
+            and_then(|_| protocol.flush()).
+            map_err(|e| Error::new(ErrorKind::Other, format!("Failed to serialize PageHeader: {}", e)))?;
+    }
+    out.extend_from_slice(data);
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use thrift::protocol::TCompactInputProtocol;
+    use reader::read_column_chunk;
+
+    fn column(type_: Type, max_def_level: i16, max_rep_level: i16) -> ColumnDescriptor {
+        ColumnDescriptor {
+            path: vec!["a".to_string(), "b".to_string()],
+            type_,
+            type_length: None,
+            max_def_level,
+            max_rep_level,
+            schema_idx: 2,
+        }
+    }
+
+    fn element(column: &ColumnDescriptor) -> SchemaElement {
+        SchemaElement::new(column.type_, column.type_length, None, column.path.last().unwrap().clone(), None, None, None, None, None, None)
+    }
+
+    fn new_writer<T: PhysicalValue>(column: &ColumnDescriptor, props: ColumnProperties) -> Result<ColumnWriter<T>> {
+        ColumnWriter::new(column.clone(), &element(column), props)
+    }
+
+    fn plain(page_size: usize) -> ColumnProperties {
+        ColumnProperties { page_size, dictionary_enabled: false, ..ColumnProperties::default() }
+    }
+
+    pub fn page_headers(data: &[u8]) -> Vec<PageHeader> {
+        let mut cursor = Cursor::new(data);
+        let mut headers = vec![];
+        while (cursor.position() as usize) < data.len() {
+            let header = PageHeader::read_from_in_protocol(&mut TCompactInputProtocol::new(&mut cursor)).unwrap();
+            let position = cursor.position() + header.compressed_page_size as u64;
+            cursor.set_position(position);
+            headers.push(header);
+        }
+        headers
+    }
+
+    fn read_back(chunk: &ChunkBuffer, column: &ColumnDescriptor) -> ::reader::ColumnData {
+        let mut file = b"PAR1".to_vec();
+        file.extend_from_slice(&chunk.data);
+        read_column_chunk(&mut Cursor::new(file), &chunk.column_chunk(4), column).unwrap()
+    }
+
+    #[test]
+    fn pages_split_on_rows() {
+        let column = column(Type::INT64, 2, 1);
+        let mut writer = new_writer::<i64>(&column, plain(16)).unwrap();
+        // rows: [1, 2, null], [], [3, 4, 5], null
+        writer.write_batch(&[1, 2], Some(&[2, 2, 1, 1]), Some(&[0, 1, 1, 0])).unwrap();
+        writer.write_batch(&[3, 4, 5], Some(&[2, 2, 2, 0]), Some(&[0, 1, 1, 0])).unwrap();
+        assert_eq!(4, writer.num_rows());
+
+        let chunk = writer.close().unwrap();
+        assert_eq!((8, 4), (chunk.meta_data().num_values, chunk.num_rows()));
+        assert_eq!(chunk.data.len() as i64, chunk.meta_data().total_compressed_size);
+        let column_chunk = chunk.column_chunk(100);
+        assert_eq!(100, column_chunk.meta_data.unwrap().data_page_offset);
+
+        let data = read_back(&chunk, &column);
+        assert_eq!(vec![2, 2, 1, 1, 2, 2, 2, 0], data.def_levels);
+        assert_eq!(vec![0, 1, 1, 0, 0, 1, 1, 0], data.rep_levels);
+        assert_eq!((1..6).map(Value::Int64).collect::<Vec<_>>(), data.values);
+        // a page is flushed once it has 16 bytes of values, before the next row
+        let pages: Vec<i32> = page_headers(&chunk.data).iter().
+            map(|h| h.data_page_header.as_ref().unwrap().num_values).collect();
+        assert_eq!(vec![3, 4, 1], pages);
+    }
+
+    #[test]
+    fn pages_of_nulls_split() {
+        let column = column(Type::INT32, 1, 0);
+        let mut writer = new_writer::<i32>(&column, plain(16)).unwrap();
+        writer.write_batch(&[], Some(&[0; 1000]), None).unwrap();
+        let chunk = writer.close().unwrap();
+        // a level takes a bit, 121 of them fill 16 bytes
+        let pages: Vec<i32> = page_headers(&chunk.data).iter().
+            map(|h| h.data_page_header.as_ref().unwrap().num_values).collect();
+        assert_eq!(vec![121, 121, 121, 121, 121, 121, 121, 121, 32], pages);
+        assert_eq!(vec![0; 1000], read_back(&chunk, &column).def_levels);
+    }
+
+    #[test]
+    fn invalid_batches() {
+        assert!(new_writer::<i32>(&column(Type::INT64, 0, 0), plain(100)).is_err());
+
+        let mut writer = new_writer::<i64>(&column(Type::INT64, 1, 0), plain(100)).unwrap();
+        assert!(writer.write_batch(&[1], None, None).is_err());
+        assert!(writer.write_batch(&[1], Some(&[1, 1]), None).is_err());
+        assert!(writer.write_batch(&[1], Some(&[2]), None).is_err());
+
+        let mut writer = new_writer::<Vec<u8>>(&column(Type::BYTE_ARRAY, 0, 1), plain(100)).unwrap();
+        assert!(writer.write_batch(&[vec![1]], None, Some(&[1])).is_err());
+        assert_eq!(2, writer.write_batch(&[vec![1], vec![2]], None, Some(&[0, 1])).unwrap());
+
+        // values of the wrong type or length are rejected before anything is buffered
+        let column = ColumnDescriptor { type_length: Some(2), ..column(Type::FIXED_LEN_BYTE_ARRAY, 0, 0) };
+        let mut writer = new_writer::<Value>(&column, plain(100)).unwrap();
+        assert!(writer.write_batch(&[Value::Bytes(vec![1, 2]), Value::Int32(1)], None, None).is_err());
+        let err = writer.write_batch(&[Value::Bytes(vec![1, 2]), Value::Bytes(vec![1, 2, 3])], None, None).unwrap_err();
+        assert_eq!(ErrorKind::InvalidInput, err.kind());
+        assert_eq!((0, 0), (writer.num_values(), writer.num_rows()));
+        writer.write_batch(&[Value::Bytes(vec![1, 2])], None, None).unwrap();
+        assert_eq!(1, writer.close().unwrap().meta_data().num_values);
+    }
+
+    #[test]
+    fn dictionary_encoding() {
+        let column = column(Type::BYTE_ARRAY, 1, 0);
+        let mut writer = new_writer::<Vec<u8>>(&column, ColumnProperties::default()).unwrap();
+        let values: Vec<Vec<u8>> = (0..100).map(|i| if i % 3 == 0 { b"abc".to_vec() } else { b"de".to_vec() }).collect();
+        let mut def_levels = vec![1; 100];
+        def_levels.push(0);
+        writer.write_batch(&values, Some(&def_levels), None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let meta = chunk.meta_data();
+        assert_eq!(Some(0), meta.dictionary_page_offset);
+        assert!(meta.data_page_offset > 0);
+        assert_eq!(vec![Encoding::PLAIN, Encoding::RLE_DICTIONARY, Encoding::RLE], meta.encodings);
+        assert_eq!(Some(vec![
+            PageEncodingStats::new(PageType::DICTIONARY_PAGE, Encoding::PLAIN, 1),
+            PageEncodingStats::new(PageType::DATA_PAGE, Encoding::RLE_DICTIONARY, 1),
+        ]), meta.encoding_stats);
+        assert_eq!(2, page_headers(&chunk.data)[0].dictionary_page_header.as_ref().unwrap().num_values);
+        // PLAIN would take over 600 bytes
+        assert!(chunk.data.len() < 100);
+
+        let data = read_back(&chunk, &column);
+        assert_eq!(values.into_iter().map(Value::Bytes).collect::<Vec<_>>(), data.values);
+    }
+
+    #[test]
+    fn write_after_finish() {
+        let column = column(Type::BYTE_ARRAY, 0, 0);
+        let mut writer = new_writer::<Vec<u8>>(&column, ColumnProperties::default()).unwrap();
+        writer.write_batch(&[b"a".to_vec(), b"b".to_vec()], None, None).unwrap();
+        writer.finish().unwrap();
+        // values new to the dictionary make the finished dictionary page out of date
+        writer.write_batch(&[b"c".to_vec()], None, None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        assert_eq!(3, page_headers(&chunk.data)[0].dictionary_page_header.as_ref().unwrap().num_values);
+        let values: Vec<Value> = [b"a", b"b", b"c"].iter().map(|v| Value::Bytes(v.to_vec())).collect();
+        assert_eq!(values, read_back(&chunk, &column).values);
+    }
+
+    #[test]
+    fn dictionary_fallback() {
+        let column = column(Type::INT64, 0, 0);
+        // dictionary of 3 values at most, pages of 2 rows
+        let props = ColumnProperties { page_size: 1, dictionary_page_size_limit: 24, ..ColumnProperties::default() };
+        let mut writer = new_writer::<i64>(&column, props).unwrap();
+        let values = vec![1, 2, 1, 3, 2, 4, 1, 5];
+        writer.write_batch(&values, None, None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let encodings: Vec<Encoding> = page_headers(&chunk.data).iter().
+            map(|h| h.data_page_header.as_ref().map_or(Encoding::PLAIN, |d| d.encoding)).collect();
+        assert!(encodings.len() > 2);
+        assert_eq!(Encoding::RLE_DICTIONARY, encodings[1]);
+        assert_eq!(Encoding::PLAIN, *encodings.last().unwrap());
+        assert_eq!(vec![Encoding::PLAIN, Encoding::RLE_DICTIONARY], chunk.meta_data().encodings);
+
+        let data = read_back(&chunk, &column);
+        assert_eq!(values.into_iter().map(Value::Int64).collect::<Vec<_>>(), data.values);
+    }
+
+    #[test]
+    fn statistics() {
+        let column = column(Type::INT64, 1, 0);
+        // rows 5, null, -3, 5, null, 8: every row fills a page
+        let mut writer = new_writer::<i64>(&column, plain(1)).unwrap();
+        writer.write_batch(&[5, -3, 5, 8], Some(&[1, 0, 1, 1, 0, 1]), None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let plain = |v: i64| Some(Value::Int64(v).to_plain());
+        let stats = chunk.meta_data().statistics.clone().unwrap();
+        assert_eq!((plain(-3), plain(8), Some(2)), (stats.min_value.clone(), stats.max_value.clone(), stats.null_count));
+        assert_eq!((stats.min_value, stats.max_value), (stats.min, stats.max));
+        assert_eq!(None, stats.distinct_count);
+
+        let pages: Vec<Statistics> = page_headers(&chunk.data).into_iter().
+            filter_map(|h| h.data_page_header.and_then(|d| d.statistics)).collect();
+        assert_eq!(vec![plain(5), None, plain(-3), plain(5), None, plain(8)],
+            pages.iter().map(|s| s.max_value.clone()).collect::<Vec<_>>());
+        assert_eq!(vec![0, 1, 0, 0, 1, 0], pages.iter().map(|s| s.null_count.unwrap()).collect::<Vec<_>>());
+
+        // distinct values are counted while the dictionary holds all of them
+        let mut writer = new_writer::<i64>(&column, ColumnProperties::default()).unwrap();
+        writer.write_batch(&[1, 2, 1], Some(&[1, 1, 1]), None).unwrap();
+        assert_eq!(Some(2), writer.close().unwrap().meta_data().statistics.as_ref().unwrap().distinct_count);
+        let props = ColumnProperties { dictionary_page_size_limit: 8, ..ColumnProperties::default() };
+        let mut writer = new_writer::<i64>(&column, props).unwrap();
+        writer.write_batch(&[1, 2, 1], Some(&[1, 1, 1]), None).unwrap();
+        assert_eq!(None, writer.close().unwrap().meta_data().statistics.as_ref().unwrap().distinct_count);
+        let doubles = self::column(Type::DOUBLE, 0, 0);
+        let mut writer = new_writer::<f64>(&doubles, ColumnProperties::default()).unwrap();
+        writer.write_batch(&[0.0, -0.0], None, None).unwrap();
+        assert_eq!(None, writer.close().unwrap().meta_data().statistics.as_ref().unwrap().distinct_count);
+
+        let props = ColumnProperties { statistics_enabled: false, ..ColumnProperties::default() };
+        let mut writer = new_writer::<i64>(&column, props).unwrap();
+        writer.write_batch(&[1], Some(&[1]), None).unwrap();
+        let chunk = writer.close().unwrap();
+        assert_eq!(None, chunk.meta_data().statistics);
+        assert_eq!(None, page_headers(&chunk.data).last().unwrap().data_page_header.as_ref().unwrap().statistics);
+    }
+
+    #[test]
+    fn statistics_column_order() {
+        let stats = |column: &ColumnDescriptor, element: &SchemaElement, values: &[Value]| {
+            let mut writer = ColumnWriter::<Value>::new(column.clone(), element, plain(DEFAULT_PAGE_SIZE)).unwrap();
+            writer.write_batch(values, None, None).unwrap();
+            writer.close().unwrap().meta_data().statistics.clone().unwrap()
+        };
+
+        let column = self::column(Type::INT32, 0, 0);
+        let mut unsigned = element(&column);
+        unsigned.logical_type = Some(LogicalType::INTEGER(IntType::new(32, false)));
+        let s = stats(&column, &unsigned, &[Value::Int32(-1), Value::Int32(1)]);
+        assert_eq!((Some(Value::Int32(1).to_plain()), Some(Value::Int32(-1).to_plain())), (s.min_value, s.max_value));
+        assert_eq!((None, None), (s.min, s.max));
+
+        let column = self::column(Type::DOUBLE, 0, 0);
+        let s = stats(&column, &element(&column), &[Value::Double(0.0), Value::Double(f64::NAN), Value::Double(-0.0)]);
+        assert_eq!((Some(Value::Double(-0.0).to_plain()), Some(Value::Double(0.0).to_plain())), (s.min_value, s.max_value));
+
+        let column = self::column(Type::BYTE_ARRAY, 0, 0);
+        let s = stats(&column, &element(&column), &[Value::Bytes(b"b".to_vec()), Value::Bytes(vec![0xff]), Value::Bytes(vec![])]);
+        assert_eq!((Some(vec![]), Some(vec![0xff])), (s.min_value, s.max_value));
+        assert_eq!(None, s.min);
+
+        let column = ColumnDescriptor { type_length: Some(2), ..self::column(Type::FIXED_LEN_BYTE_ARRAY, 0, 0) };
+        let mut float16 = element(&column);
+        float16.logical_type = Some(LogicalType::FLOAT16(Float16Type::new()));
+        // 2.0, -1.0 and -0.0
+        let s = stats(&column, &float16, &[Value::Bytes(vec![0, 0x40]), Value::Bytes(vec![0, 0xbc]), Value::Bytes(vec![0, 0x80])]);
+        assert_eq!((Some(vec![0, 0xbc]), Some(vec![0, 0x40])), (s.min_value, s.max_value));
+
+        let column = self::column(Type::INT96, 0, 0);
+        let s = stats(&column, &element(&column), &[Value::Int96([1, 2, 3])]);
+        assert_eq!((None, None, Some(0)), (s.min_value, s.max_value, s.null_count));
+    }
+
+    #[test]
+    fn page_index() {
+        let column = column(Type::INT64, 1, 0);
+        // pages of a row each: [1], [null], [2], [2], [null], [7]
+        let mut writer = new_writer::<i64>(&column, plain(1)).unwrap();
+        writer.write_batch(&[1, 2, 2], Some(&[1, 0, 1, 1, 0]), None).unwrap();
+        writer.write_batch(&[7], Some(&[1]), None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let offsets = chunk.offset_index(100);
+        assert_eq!(vec![0, 1, 2, 3, 4, 5], offsets.page_locations.iter().map(|l| l.first_row_index).collect::<Vec<_>>());
+        assert_eq!(100, offsets.page_locations[0].offset);
+        let last = offsets.page_locations.last().unwrap();
+        assert_eq!(100 + chunk.data.len() as i64, last.offset + last.compressed_page_size as i64);
+
+        let index = chunk.column_index().unwrap();
+        assert_eq!(BoundaryOrder::ASCENDING, index.boundary_order);
+        assert_eq!(vec![false, true, false, false, true, false], index.null_pages);
+        assert_eq!(Some(vec![0, 1, 0, 0, 1, 0]), index.null_counts);
+
+        let index_of = |values: &[i64], def_levels: &[i16]| {
+            let mut writer = new_writer::<i64>(&column, plain(1)).unwrap();
+            writer.write_batch(values, Some(def_levels), None).unwrap();
+            writer.close().unwrap().column_index().cloned().unwrap()
+        };
+        let index = index_of(&[3, 2], &[1, 1, 0]);
+        assert_eq!(BoundaryOrder::DESCENDING, index.boundary_order);
+        assert_eq!(vec![false, false, true], index.null_pages);
+        assert_eq!(Vec::<u8>::new(), index.min_values[2]);
+        assert_eq!(BoundaryOrder::UNORDERED, index_of(&[3, 2, 4], &[1, 1, 1]).boundary_order);
+
+        // pages of NaN only have no bounds
+        let column = self::column(Type::DOUBLE, 0, 0);
+        let mut writer = new_writer::<f64>(&column, plain(1)).unwrap();
+        writer.write_batch(&[1.0, f64::NAN], None, None).unwrap();
+        let chunk = writer.close().unwrap();
+        assert_eq!(None, chunk.column_index());
+        assert_eq!(2, chunk.offset_index(0).page_locations.len());
+    }
+
+    #[test]
+    fn page_index_truncation() {
+        let column = column(Type::BYTE_ARRAY, 0, 0);
+        let long = vec![b'a'; 100];
+        let props = ColumnProperties { column_index_truncate_length: Some(4), ..plain(DEFAULT_PAGE_SIZE) };
+        let mut writer = new_writer::<Vec<u8>>(&column, props).unwrap();
+        writer.write_batch(&[long.clone(), b"b".to_vec()], None, None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let index = chunk.column_index().unwrap();
+        assert_eq!((b"aaaa".to_vec(), b"b".to_vec()), (index.min_values[0].clone(), index.max_values[0].clone()));
+        // statistics are not truncated
+        assert_eq!(Some(long), chunk.meta_data().statistics.as_ref().unwrap().min_value);
+    }
+
+    #[test]
+    fn delta_encodings() {
+        let column = column(Type::INT64, 1, 0);
+        let props = ColumnProperties { encoding: Encoding::DELTA_BINARY_PACKED, ..plain(DEFAULT_PAGE_SIZE) };
+        let mut writer = new_writer::<i64>(&column, props).unwrap();
+        let values: Vec<i64> = (0..1000).map(|i| 1_000_000 + i * 10).collect();
+        writer.write_batch(&values, Some(&[1; 1000]), None).unwrap();
+        let chunk = writer.close().unwrap();
+        assert_eq!(vec![Encoding::DELTA_BINARY_PACKED, Encoding::RLE], chunk.meta_data().encodings);
+        // deltas are packed with 0 bits, PLAIN would take 8000 bytes
+        assert!(chunk.data.len() < 200);
+        assert_eq!(values.into_iter().map(Value::Int64).collect::<Vec<_>>(), read_back(&chunk, &column).values);
+
+        let column = self::column(Type::BYTE_ARRAY, 0, 0);
+        let props = ColumnProperties { encoding: Encoding::DELTA_BYTE_ARRAY, ..plain(DEFAULT_PAGE_SIZE) };
+        let mut writer = new_writer::<Vec<u8>>(&column, props).unwrap();
+        let values: Vec<Vec<u8>> = (0..100).map(|i| format!("key-{:05}", i).into_bytes()).collect();
+        writer.write_batch(&values, None, None).unwrap();
+        let chunk = writer.close().unwrap();
+        assert_eq!(values.into_iter().map(Value::Bytes).collect::<Vec<_>>(), read_back(&chunk, &column).values);
+
+        let props = ColumnProperties { encoding: Encoding::RLE, ..plain(DEFAULT_PAGE_SIZE) };
+        assert!(new_writer::<bool>(&self::column(Type::BOOLEAN, 0, 0), props.clone()).is_err());
+        let props = ColumnProperties { writer_version: WriterVersion::V2, ..props };
+        let mut writer = new_writer::<bool>(&self::column(Type::BOOLEAN, 0, 0), props.clone()).unwrap();
+        writer.write_batch(&[true; 20], None, None).unwrap();
+        let data = read_back(&writer.close().unwrap(), &self::column(Type::BOOLEAN, 0, 0));
+        assert_eq!(vec![Value::Boolean(true); 20], data.values);
+
+        assert!(new_writer::<i64>(&self::column(Type::INT64, 0, 0), props).is_err());
+        let props = ColumnProperties { encoding: Encoding::DELTA_BINARY_PACKED, ..plain(DEFAULT_PAGE_SIZE) };
+        assert!(new_writer::<Vec<u8>>(&self::column(Type::BYTE_ARRAY, 0, 0), props).is_err());
+    }
+
+    #[test]
+    fn estimated_sizes() {
+        let column = column(Type::INT64, 1, 0);
+        let mut writer = new_writer::<i64>(&column, plain(1024)).unwrap();
+        assert_eq!(0, writer.estimated_size());
+        writer.write_batch(&[1, 2, 3], Some(&[1, 1, 0, 1]), None).unwrap();
+        // buffered values and levels of the open page
+        let buffered = writer.estimated_size();
+        assert!((24..1024).contains(&buffered));
+        assert!(writer.memory_size() >= buffered);
+
+        let values: Vec<i64> = (0..1000).collect();
+        writer.write_batch(&values, Some(&[1; 1000]), None).unwrap();
+        let estimated = writer.estimated_size();
+        let memory = writer.memory_size();
+        let chunk = writer.close().unwrap();
+        // the estimate is within an open page of the chunk size
+        assert!(estimated <= chunk.data.len() + 1024 && chunk.data.len() <= estimated + 1024);
+        assert!(memory >= chunk.data.len());
+
+        let mut writer = new_writer::<i64>(&column, ColumnProperties::default()).unwrap();
+        writer.write_batch(&[7; 100], Some(&[1; 100]), None).unwrap();
+        // a small dictionary keeps the estimate well below PLAIN size of values
+        assert!(writer.estimated_size() < 800);
+    }
+
+    #[test]
+    fn compressed_pages() {
+        let column = column(Type::INT64, 1, 0);
+        let props = ColumnProperties { compression: CompressionCodec::SNAPPY, ..plain(1024) };
+        let mut writer = new_writer::<i64>(&column, props).unwrap();
+        let values: Vec<i64> = (0..1000).map(|i| i % 4).collect();
+        writer.write_batch(&values, Some(&[1; 1000]), None).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let headers = page_headers(&chunk.data);
+        assert!(headers.len() > 1);
+        assert!(headers.iter().all(|h| h.compressed_page_size < h.uncompressed_page_size));
+        let meta = chunk.meta_data();
+        assert_eq!(CompressionCodec::SNAPPY, meta.codec);
+        assert_eq!(chunk.data.len() as i64, meta.total_compressed_size);
+        let page_sizes: i32 = headers.iter().map(|h| h.uncompressed_page_size - h.compressed_page_size).sum();
+        assert_eq!(meta.total_compressed_size + page_sizes as i64, meta.total_uncompressed_size);
+        assert_eq!(values.into_iter().map(Value::Int64).collect::<Vec<_>>(), read_back(&chunk, &column).values);
+
+        let props = ColumnProperties { compression: CompressionCodec::BROTLI, ..plain(1024) };
+        assert!(new_writer::<i64>(&column, props).is_err());
+    }
+
+    #[test]
+    fn data_pages_v2() {
+        let column = column(Type::INT64, 2, 1);
+        let props = ColumnProperties { writer_version: WriterVersion::V2, compression: CompressionCodec::SNAPPY, ..plain(16) };
+        let mut writer = new_writer::<i64>(&column, props.clone()).unwrap();
+        // rows: [1, 2, null], [], [3, 4, 5], null
+        writer.write_batch(&[1, 2], Some(&[2, 2, 1, 1]), Some(&[0, 1, 1, 0])).unwrap();
+        writer.write_batch(&[3, 4, 5], Some(&[2, 2, 2, 0]), Some(&[0, 1, 1, 0])).unwrap();
+        let chunk = writer.close().unwrap();
+
+        let headers = page_headers(&chunk.data);
+        assert!(headers.iter().all(|h| h.type_ == PageType::DATA_PAGE_V2 && h.data_page_header.is_none()));
+        let v2: Vec<_> = headers.iter().map(|h| h.data_page_header_v2.as_ref().unwrap()).collect();
+        assert_eq!(vec![(3, 1, 1), (4, 1, 2), (1, 1, 1)], v2.iter().map(|h| (h.num_values, h.num_nulls, h.num_rows)).collect::<Vec<_>>());
+        // two values, or none, do not compress
+        assert_eq!((Some(false), Some(false)), (v2[0].is_compressed, v2[2].is_compressed));
+        assert_eq!(PageType::DATA_PAGE_V2, chunk.meta_data().encoding_stats.as_ref().unwrap()[0].page_type);
+        let data = read_back(&chunk, &column);
+        assert_eq!(vec![0, 1, 1, 0, 0, 1, 1, 0], data.rep_levels);
+        assert_eq!((1..6).map(Value::Int64).collect::<Vec<_>>(), data.values);
+
+        let mut writer = new_writer::<i64>(&column, ColumnProperties { page_size: DEFAULT_PAGE_SIZE, ..props }).unwrap();
+        writer.write_batch(&[7; 200], Some(&[2; 200]), Some(&[0; 200])).unwrap();
+        let chunk = writer.close().unwrap();
+        let header = &page_headers(&chunk.data)[0];
+        assert_eq!(Some(true), header.data_page_header_v2.as_ref().unwrap().is_compressed);
+        assert!(header.compressed_page_size < header.uncompressed_page_size);
+        assert_eq!(vec![Value::Int64(7); 200], read_back(&chunk, &column).values);
+    }
+}
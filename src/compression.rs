@@ -0,0 +1,86 @@
+use std::io::{Result, Error, ErrorKind};
+use snap;
+use parquet::CompressionCodec;
+
+/// Page compression algorithm
+pub trait Codec {
+    fn compress(&mut self, input: &[u8]) -> Result<Vec<u8>>;
+    fn decompress(&mut self, input: &[u8], uncompressed_size: usize) -> Result<Vec<u8>>;
+}
+
+/// Codec implementation for given `CompressionCodec`, None if it is not supported yet.
+/// UNCOMPRESSED does not have a codec.
+pub fn create_codec(codec: CompressionCodec) -> Option<Box<dyn Codec>> {
+    match codec {
+        CompressionCodec::SNAPPY => Some(Box::new(SnappyCodec::new())),
+        _ => None,
+    }
+}
+
+/// Compress page data with `codec`. Returns None if that does not make it smaller,
+/// so that pages which may be stored uncompressed (v2 data pages) can be.
+pub fn compress_if_smaller(codec: &mut dyn Codec, input: &[u8]) -> Result<Option<Vec<u8>>> {
+    let compressed = codec.compress(input)?;
+    Ok(if compressed.len() < input.len() { Some(compressed) } else { None })
+}
+
+/// Decompress page data with the column chunk's codec
+pub fn decompress(codec: CompressionCodec, input: &[u8], uncompressed_size: usize) -> Result<Vec<u8>> {
+    if codec == CompressionCodec::UNCOMPRESSED {
+        return Ok(input.to_vec())
+    }
+
+    match create_codec(codec) {
+        Some(mut codec) => codec.decompress(input, uncompressed_size),
+        None => Err(Error::new(ErrorKind::Other, format!("Compression is not implemented yet: {:?}", codec))),
+    }
+}
+
+pub struct SnappyCodec {
+    encoder: snap::Encoder,
+    decoder: snap::Decoder,
+}
+
+impl SnappyCodec {
+    pub fn new() -> SnappyCodec {
+        SnappyCodec { encoder: snap::Encoder::new(), decoder: snap::Decoder::new() }
+    }
+}
+
+impl Default for SnappyCodec {
+    fn default() -> SnappyCodec {
+        SnappyCodec::new()
+    }
+}
+
+impl Codec for SnappyCodec {
+    fn compress(&mut self, input: &[u8]) -> Result<Vec<u8>> {
+        self.encoder.compress_vec(input).
+            map_err(|e| Error::new(ErrorKind::Other, format!("Snappy compression failed: {}", e)))
+    }
+
+    fn decompress(&mut self, input: &[u8], uncompressed_size: usize) -> Result<Vec<u8>> {
+        let mut output = vec![0_u8; uncompressed_size];
+        let len = self.decoder.decompress(input, &mut output).
+            map_err(|e| Error::new(ErrorKind::InvalidData, format!("Snappy decompression failed: {}", e)))?;
+        output.truncate(len);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snappy_roundtrip() {
+        let mut codec = create_codec(CompressionCodec::SNAPPY).unwrap();
+        let input = b"abcabcabcabcabcabcabcabcabcabcabc".to_vec();
+        let compressed = compress_if_smaller(&mut *codec, &input).unwrap().unwrap();
+        assert!(compressed.len() < input.len());
+        assert_eq!(input, decompress(CompressionCodec::SNAPPY, &compressed, input.len()).unwrap());
+
+        assert_eq!(None, compress_if_smaller(&mut *codec, b"abc").unwrap());
+        assert!(create_codec(CompressionCodec::BROTLI).is_none());
+    }
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+use parquet::*;
+use value::Value;
+use logical::{Logical, resolve};
+
+/// Decimal value: unscaled integer with precision and scale from the schema.
+/// Numeric value is `unscaled * 10^-scale`. Byte array columns may have a precision over 38 digits,
+/// their values are read as long as they fit 128 bits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Decimal {
+    unscaled: i128,
+    precision: i32,
+    scale: i32,
+}
+
+impl Decimal {
+    pub fn new(unscaled: i128, precision: i32, scale: i32) -> Result<Decimal, String> {
+        if precision <= 0 {
+            return Err(format!("Decimal precision must be positive but got {}", precision))
+        }
+        if scale < 0 || scale > precision {
+            return Err(format!("Decimal scale must be in 0..{} but got {}", precision, scale))
+        }
+        Ok(Decimal { unscaled, precision, scale })
+    }
+
+    /// Decode physical value of a DECIMAL column: INT32, INT64 or big-endian two's complement bytes.
+    pub fn from_value(value: &Value, precision: i32, scale: i32) -> Result<Decimal, String> {
+        let unscaled = match *value {
+            Value::Int32(v) => v as i128,
+            Value::Int64(v) => v as i128,
+            Value::Bytes(ref bytes) => from_be_bytes(bytes)?,
+            _ => return Err(format!("Value can not be a decimal: {:?}", value)),
+        };
+        Decimal::new(unscaled, precision, scale)
+    }
+
+    /// Encode as physical value of a DECIMAL column of `type_`, inverse of `from_value`.
+    /// Byte arrays are as short as possible, fixed length ones are sign extended to `type_length`.
+    pub fn to_value(&self, type_: Type, type_length: i32) -> Result<Value, String> {
+        let too_large = || format!("Decimal {} does not fit {:?} (length {})", self, type_, type_length);
+        match type_ {
+            Type::INT32 if self.unscaled >= i32::MIN as i128 && self.unscaled <= i32::MAX as i128 =>
+                Ok(Value::Int32(self.unscaled as i32)),
+            Type::INT64 if self.unscaled >= i64::MIN as i128 && self.unscaled <= i64::MAX as i128 =>
+                Ok(Value::Int64(self.unscaled as i64)),
+            Type::BYTE_ARRAY => Ok(Value::Bytes(to_be_bytes(self.unscaled, min_bytes(self.unscaled)))),
+            Type::FIXED_LEN_BYTE_ARRAY if type_length >= 0 && min_bytes(self.unscaled) <= type_length as usize =>
+                Ok(Value::Bytes(to_be_bytes(self.unscaled, type_length as usize))),
+            _ => Err(too_large()),
+        }
+    }
+
+    pub fn unscaled(&self) -> i128 { self.unscaled }
+    pub fn precision(&self) -> i32 { self.precision }
+    pub fn scale(&self) -> i32 { self.scale }
+
+    /// Convert to `rust_decimal::Decimal`, None if value does not fit its 96 bit mantissa or 28 digits scale
+    #[cfg(feature = "rust_decimal")]
+    pub fn to_rust_decimal(&self) -> Option<::rust_decimal::Decimal> {
+        ::rust_decimal::Decimal::try_from_i128_with_scale(self.unscaled, self.scale as u32).ok()
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = self.unscaled.abs().to_string();
+        let sign = if self.unscaled < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits)
+        }
+
+        let digits = if digits.len() <= scale {
+            format!("{}{}", "0".repeat(scale - digits.len() + 1), digits)
+        } else {
+            digits
+        };
+        let (int, frac) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, int, frac)
+    }
+}
+
+/// Precision and scale of a DECIMAL column from its resolved logical type, fails when the annotation is invalid
+pub fn decimal_params(element: &SchemaElement) -> Result<Option<(i32, i32)>, String> {
+    Ok(match resolve(element)? {
+        Some(Logical::Decimal { precision, scale }) => Some((precision, scale)),
+        _ => None,
+    })
+}
+
+/// Decode values of a DECIMAL column
+pub fn decode_decimals(element: &SchemaElement, values: &[Value]) -> Result<Vec<Decimal>, String> {
+    let (precision, scale) = decimal_params(element)?.
+        ok_or_else(|| format!("Column '{}' is not a decimal", element.name))?;
+    values.iter().map(|v| Decimal::from_value(v, precision, scale)).collect()
+}
+
+/// Big-endian two's complement integer, of over 16 bytes only if it is sign extended from 16 bytes
+fn from_be_bytes(bytes: &[u8]) -> Result<i128, String> {
+    if bytes.is_empty() {
+        return Ok(0)
+    }
+    let len = bytes.len();
+    let (extension, bytes) = bytes.split_at(len.saturating_sub(16));
+    let sign = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+    if extension.iter().any(|&b| b != sign) {
+        return Err(format!("Decimal of {} bytes does not fit 128 bits", len))
+    }
+
+    let mut res: i128 = if bytes[0] & 0x80 != 0 { -1 } else { 0 };
+    for &b in bytes {
+        res = (res << 8) | b as i128;
+    }
+    Ok(res)
+}
+
+/// Number of bytes of the shortest two's complement form
+fn min_bytes(value: i128) -> usize {
+    let significant = if value < 0 { 128 - (!value).leading_zeros() } else { 128 - value.leading_zeros() };
+    significant as usize / 8 + 1
+}
+
+/// Big-endian two's complement integer of `len` bytes, sign extended beyond 16 bytes
+fn to_be_bytes(value: i128, len: usize) -> Vec<u8> {
+    (0..len).rev().map(|i| if i < 16 { (value >> (i * 8)) as u8 } else if value < 0 { 0xff } else { 0 }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_physical() {
+        assert_eq!(-12345, Decimal::from_value(&Value::Int32(-12345), 9, 2).unwrap().unscaled());
+        assert_eq!(-2, Decimal::from_value(&Value::Bytes(vec![0xff, 0xfe]), 5, 0).unwrap().unscaled());
+        assert_eq!(0x0100, Decimal::from_value(&Value::Bytes(vec![0x00, 0x01, 0x00]), 5, 0).unwrap().unscaled());
+        assert!(Decimal::from_value(&Value::Bytes(vec![1; 17]), 38, 0).is_err());
+        // FIXED_LEN_BYTE_ARRAY(17) sign extended from 16 bytes, of precision over 38
+        let mut small = vec![0; 17];
+        small[16] = 42;
+        assert_eq!(42, Decimal::from_value(&Value::Bytes(small), 40, 0).unwrap().unscaled());
+        assert_eq!(-2, Decimal::from_value(&Value::Bytes(vec![0xff, 0xff, 0xfe]), 40, 0).unwrap().unscaled());
+        let mut negative = vec![0xff; 17];
+        negative[16] = 0xfe;
+        assert_eq!(-2, Decimal::from_value(&Value::Bytes(negative), 40, 0).unwrap().unscaled());
+        // 2^127 needs a 17th byte
+        let mut large = vec![0; 17];
+        large[1] = 0x80;
+        assert!(Decimal::from_value(&Value::Bytes(large), 40, 0).is_err());
+        assert!(Decimal::from_value(&Value::Int32(1), 5, 6).is_err());
+    }
+
+    #[test]
+    fn encode_physical() {
+        let d = |unscaled| Decimal::new(unscaled, 38, 0).unwrap();
+        assert_eq!(Value::Int32(-12345), d(-12345).to_value(Type::INT32, 0).unwrap());
+        assert!(d(1 << 40).to_value(Type::INT32, 0).is_err());
+        assert_eq!(Value::Bytes(vec![0xff, 0xfe]), d(-2).to_value(Type::FIXED_LEN_BYTE_ARRAY, 2).unwrap());
+        assert_eq!(Value::Bytes(vec![0x00, 0x80]), d(128).to_value(Type::BYTE_ARRAY, 0).unwrap());
+        assert_eq!(Value::Bytes(vec![0x80]), d(-128).to_value(Type::BYTE_ARRAY, 0).unwrap());
+        assert!(d(128).to_value(Type::FIXED_LEN_BYTE_ARRAY, 1).is_err());
+        assert_eq!(Value::Bytes(vec![0xff; 17]), d(-1).to_value(Type::FIXED_LEN_BYTE_ARRAY, 17).unwrap());
+        for &v in &[0, 1, -1, 255, -256, i128::MAX, i128::MIN] {
+            let value = d(v).to_value(Type::BYTE_ARRAY, 0).unwrap();
+            assert_eq!(v, Decimal::from_value(&value, 38, 0).unwrap().unscaled());
+        }
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!("123.45", Decimal::new(12345, 5, 2).unwrap().to_string());
+        assert_eq!("-0.005", Decimal::new(-5, 5, 3).unwrap().to_string());
+        assert_eq!("42", Decimal::new(42, 5, 0).unwrap().to_string());
+    }
+
+    #[cfg(feature = "rust_decimal")]
+    #[test]
+    fn rust_decimal_conversion() {
+        let d = Decimal::new(-12345, 10, 3).unwrap().to_rust_decimal().unwrap();
+        assert_eq!("-12.345", d.to_string());
+        assert!(Decimal::new(i128::MAX, 38, 0).unwrap().to_rust_decimal().is_none());
+    }
+
+    #[test]
+    fn params_from_schema() {
+        let logical = SchemaElement::new(Type::INT64, None, FieldRepetitionType::REQUIRED, "d".to_string(), None,
+            None, None, None, None, LogicalType::DECIMAL(DecimalType::new(2, 10)));
+        assert_eq!(Ok(Some((10, 2))), decimal_params(&logical));
+
+        let converted = SchemaElement::new(Type::FIXED_LEN_BYTE_ARRAY, 16, FieldRepetitionType::REQUIRED, "d".to_string(), None,
+            ConvertedType::DECIMAL, 4, 38, None, None);
+        assert_eq!(Ok(Some((38, 4))), decimal_params(&converted));
+
+        let mut no_precision = converted.clone();
+        no_precision.precision = None;
+        assert!(decimal_params(&no_precision).is_err());
+        assert!(decode_decimals(&no_precision, &[]).unwrap_err().contains("does not have precision"));
+    }
+}
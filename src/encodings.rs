@@ -1,4 +1,6 @@
 use byteorder::{ByteOrder, LittleEndian};
+use parquet::{Type, Encoding};
+use value::Value;
 
 #[derive(Debug)]
 pub struct BitPackingRleReader<'a> {
@@ -14,7 +16,6 @@ impl<'a> BitPackingRleReader<'a> {
         if data.len() < 4 {return Err("Failed to read RLE encoding length".to_string())}
 
         let len_encoded = LittleEndian::read_u32(data);
-        println!(">>> len_encoded: {0}", len_encoded);
         let pos = 4_usize;
 
         if pos + len_encoded as usize > data.len() {
@@ -35,6 +36,11 @@ impl<'a> BitPackingRleReader<'a> {
             next: 4,
         })
     }
+
+    /// Bytes taken by encoded data including length prefix
+    pub fn encoded_len(&self) -> usize {
+        self.compressed_len as usize + 4
+    }
  }
 
 impl<'a> IntoIterator for BitPackingRleReader<'a> {
@@ -42,72 +48,95 @@ impl<'a> IntoIterator for BitPackingRleReader<'a> {
     type IntoIter = RleIter<'a>;
 
     fn into_iter(self) -> Self::IntoIter {
-        println!(">>> 1");
-        RleIter::new(&self.data[3 .. (self.compressed_len + 4) as usize],
-            self.byte_width
+        RleIter::new(&self.data[4 .. (self.compressed_len + 4) as usize],
+            self.bit_width
         )
     }
 }
 
+/// Iterator over RLE/bit-packed hybrid encoded values, without length prefix.
+/// Last bit-packed run may be padded, so caller should limit by expected values count.
 pub struct RleIter<'a> {
-    ptr: u32,
-    counter: i32,
-    value: i32,
+    pos: usize,
     data: &'a [u8],
-    byte_width: u32
+    bit_width: u32,
+    // values left in current run
+    rle_left: u32,
+    rle_value: i32,
+    packed_left: u32,
+    packed_bit_pos: usize,
 }
 
 impl<'a> Iterator for RleIter<'a> {
     type Item = i32;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.counter > 0 {
-            self.counter -= 1;
-            return Some(self.value);
-        } else if self.counter == 0 {
-            self.read_next()
-        }
+        loop {
+            if self.rle_left > 0 {
+                self.rle_left -= 1;
+                return Some(self.rle_value);
+            }
 
-        if self.counter == -1 {
-            return None
-        }
+            if self.packed_left > 0 {
+                self.packed_left -= 1;
+                let value = read_bits(self.data, self.packed_bit_pos, self.bit_width);
+                self.packed_bit_pos += self.bit_width as usize;
+                return value;
+            }
 
-        None
+            if !self.read_next() {
+                return None
+            }
+        }
     }
 }
 
 impl<'a> RleIter<'a> {
-    fn new(data: &'a[u8], byte_width: u32) -> RleIter {
+    pub fn new(data: &'a[u8], bit_width: u32) -> RleIter<'a> {
         RleIter {
-            ptr: 0,
-            counter: 0,
+            pos: 0,
             data,
-            value: 0,
-            byte_width,
+            bit_width,
+            rle_left: 0,
+            rle_value: 0,
+            packed_left: 0,
+            packed_bit_pos: 0,
         }
     }
 
-    fn read_next(&mut self) {
-        if self.counter != 0 {
-            self.counter -= 1
-        } else {
-            let mut pos = 0_usize;
-            let header: u32 = read_leb128(self.data, &mut pos);
-            let mode = if header & 1 == 1 { Mode::Packed } else { Mode::Rle };
-            println!("Pack mode: {:?} header: {}", mode, header);
-            match mode {
-                Mode::Rle => {
-                    let repeated = header >> 1;
-                    let val = read_bitpack_int(self.byte_width, self.data, & mut pos).
-                        expect("Failed to decode RLE value");
-                    println!("RLE decoding: repeated: {} val: {}", repeated, val);
+    /// Read next run header. Returns false when data is exhausted.
+    fn read_next(&mut self) -> bool {
+        if self.pos >= self.data.len() {
+            return false
+        }
+
+        let header: u32 = match read_leb128(self.data, &mut self.pos) {
+            Some(header) => header,
+            None => return false,
+        };
+        let mode = if header & 1 == 1 { Mode::Packed } else { Mode::Rle };
+        match mode {
+            Mode::Rle => {
+                let byte_width = byte_width(self.bit_width) as usize;
+                if self.pos + byte_width > self.data.len() {
+                    return false
                 }
-                Mode::Packed => {
-                    //return Err("Mode::Packed not implemented yet".to_string());
-                    unimplemented!("Mode::Packed")
+                let mut value = 0_u32;
+                for i in 0..byte_width {
+                    value |= (self.data[self.pos + i] as u32) << (i * 8);
                 }
+                self.pos += byte_width;
+                self.rle_left = header >> 1;
+                self.rle_value = value as i32;
+            }
+            Mode::Packed => {
+                let groups = (header >> 1) as usize;
+                self.packed_left = (groups * 8) as u32;
+                self.packed_bit_pos = self.pos * 8;
+                self.pos += groups * self.bit_width as usize;
             }
         }
+        true
     }
 }
 
@@ -138,7 +167,7 @@ enum Mode {
     Packed
 }
 
-fn bit_width(max_int: u32) -> u32 {
+pub fn bit_width(max_int: u32) -> u32 {
     32 - max_int.leading_zeros()
 }
 fn byte_width(bit_width: u32) -> u32 {
@@ -150,23 +179,40 @@ fn round_to_byte(bits: u32) -> u32 {
     (bits + 7) / 8
 }
 
-fn read_leb128(data: &[u8], offset: &mut usize) -> u32 {
+fn read_leb128(data: &[u8], offset: &mut usize) -> Option<u32> {
     let mut res = 0_u32;
     let mut pos = *offset;
     let mut shift = 0;
 
-    let res = loop {
-        let byte = data[pos];
+    loop {
+        let byte = *data.get(pos)?;
+        if shift > 28 { return None }
         res |= (byte as u32 & 0x7f) << shift;
         shift += 7;
         pos += 1;
         if (byte as u32 & 0x80) == 0 {
-            break res
+            break
         }
-    };
+    }
 
     *offset = pos;
-    res
+    Some(res)
+}
+
+/// Read `bit_width` bits starting at `bit_pos`, least significant bit first
+fn read_bits(data: &[u8], bit_pos: usize, bit_width: u32) -> Option<i32> {
+    if bit_width == 0 { return Some(0) }
+    if bit_pos + bit_width as usize > data.len() * 8 { return None }
+
+    let mut res = 0_u64;
+    let first_byte = bit_pos / 8;
+    let last_byte = (bit_pos + bit_width as usize - 1) / 8;
+    for (i, byte) in data[first_byte ..= last_byte].iter().enumerate() {
+        res |= (*byte as u64) << (i * 8);
+    }
+    res >>= bit_pos % 8;
+    res &= (1_u64 << bit_width) - 1;
+    Some(res as i32)
 }
 
 fn read_bitpack_int(bit_width: u32, data: &[u8], offset: &mut usize) -> Result<i32,String> {
@@ -191,6 +237,503 @@ fn read_bitpack_int(bit_width: u32, data: &[u8], offset: &mut usize) -> Result<i
 }
 
 
+//
+// Plain decoding
+//
+
+/// Decode `count` PLAIN encoded values. Returns values and number of bytes consumed.
+pub fn decode_plain(type_: Type, type_length: i32, data: &[u8], count: usize) -> Result<(Vec<Value>, usize),String> {
+    let mut values = Vec::with_capacity(count);
+    let mut pos = 0_usize;
+
+    let fixed = |len: usize| {
+        if count * len > data.len() {
+            Err(format!("Unexpected end of PLAIN data: {} {:?} values need {} bytes but got {}",
+                count, type_, count * len, data.len()))
+        } else {
+            Ok(())
+        }
+    };
+
+    match type_ {
+        Type::BOOLEAN => {
+            if count.div_ceil(8) > data.len() {
+                return Err(format!("Unexpected end of PLAIN data: {} booleans but got {} bytes", count, data.len()))
+            }
+            for i in 0..count {
+                values.push(Value::Boolean(data[i / 8] >> (i % 8) & 1 == 1));
+            }
+            pos = count.div_ceil(8);
+        },
+        Type::BYTE_ARRAY => {
+            for _ in 0..count {
+                if pos + 4 > data.len() { return Err("Unexpected end of PLAIN data: can't read byte array length".to_string()) }
+                let len = LittleEndian::read_u32(&data[pos..]) as usize;
+                pos += 4;
+                if pos + len > data.len() {
+                    return Err(format!("Unexpected end of PLAIN data: byte array of {} bytes but {} left", len, data.len() - pos))
+                }
+                values.push(Value::Bytes(data[pos .. pos + len].to_vec()));
+                pos += len;
+            }
+        },
+        _ => {
+            let len = match type_ {
+                Type::INT32 | Type::FLOAT => 4,
+                Type::INT64 | Type::DOUBLE => 8,
+                Type::INT96 => 12,
+                _ => {
+                    if type_length <= 0 { return Err(format!("Invalid FIXED_LEN_BYTE_ARRAY length {}", type_length)) }
+                    type_length as usize
+                }
+            };
+            fixed(len)?;
+            for _ in 0..count {
+                values.push(Value::from_plain(type_, &data[pos .. pos + len])?);
+                pos += len;
+            }
+        }
+    }
+
+    Ok((values, pos))
+}
+
+/// PLAIN encode values of a column of `type_`, appending to `out`
+pub fn encode_plain(type_: Type, type_length: i32, values: &[Value], out: &mut Vec<u8>) -> Result<(), String> {
+    if type_ == Type::BOOLEAN {
+        let start = out.len();
+        out.resize(start + values.len().div_ceil(8), 0);
+        for (i, value) in values.iter().enumerate() {
+            match *value {
+                Value::Boolean(v) => out[start + i / 8] |= (v as u8) << (i % 8),
+                _ => return Err(format!("Expected BOOLEAN value but got {:?}", value)),
+            }
+        }
+        return Ok(())
+    }
+
+    for value in values {
+        match (type_, value) {
+            (Type::INT32, Value::Int32(_)) | (Type::INT64, Value::Int64(_)) | (Type::INT96, Value::Int96(_)) |
+            (Type::FLOAT, Value::Float(_)) | (Type::DOUBLE, Value::Double(_)) => out.extend(value.to_plain()),
+            (Type::BYTE_ARRAY, Value::Bytes(bytes)) => {
+                let mut len = [0_u8; 4];
+                LittleEndian::write_u32(&mut len, bytes.len() as u32);
+                out.extend_from_slice(&len);
+                out.extend_from_slice(bytes);
+            },
+            (Type::FIXED_LEN_BYTE_ARRAY, Value::Bytes(bytes)) if bytes.len() == type_length as usize =>
+                out.extend_from_slice(bytes),
+            _ => return Err(format!("Value {:?} does not match column type {:?} (length {})", value, type_, type_length)),
+        }
+    }
+    Ok(())
+}
+
+/// RLE/bit-packed hybrid encoding of `values`, without length prefix.
+/// Repeats of 8 or more values become RLE runs, the rest is bit-packed in groups of 8.
+pub fn encode_rle(values: &[u32], bit_width: u32) -> Vec<u8> {
+    let mut out = vec![];
+    // values waiting to be bit-packed, a run can only start after a whole group
+    let mut literals: Vec<u64> = vec![];
+    let mut i = 0;
+    while i < values.len() {
+        let run = values[i..].iter().take_while(|&&v| v == values[i]).count();
+        if run < 8 {
+            literals.extend(values[i .. i + run].iter().map(|&v| v as u64));
+            i += run;
+        } else if literals.len() % 8 != 0 {
+            // complete the group with the first values of the run
+            let fill = 8 - literals.len() % 8;
+            literals.extend(values[i .. i + fill].iter().map(|&v| v as u64));
+            i += fill;
+        } else {
+            write_bit_packed_runs(&literals, bit_width, &mut out);
+            literals.clear();
+            write_leb128((run as u64) << 1, &mut out);
+            for byte in 0..byte_width(bit_width) {
+                out.push((values[i] >> (byte * 8)) as u8);
+            }
+            i += run;
+        }
+    }
+    write_bit_packed_runs(&literals, bit_width, &mut out);
+    out
+}
+
+/// Bit-packed runs of `values`, the last group padded with zeros
+fn write_bit_packed_runs(values: &[u64], bit_width: u32, out: &mut Vec<u8>) {
+    // at most 63 groups per run, so the header takes a single byte as with other writers
+    for run in values.chunks(63 * 8) {
+        let groups = run.len().div_ceil(8);
+        write_leb128((groups as u64) << 1 | 1, out);
+        write_bits(run, groups * 8, bit_width, out);
+    }
+}
+
+fn write_leb128(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+fn read_leb128_u64(data: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut res = 0_u64;
+    let mut pos = *offset;
+    let mut shift = 0;
+
+    loop {
+        let byte = *data.get(pos)?;
+        if shift > 63 { return None }
+        res |= (byte as u64 & 0x7f) << shift;
+        shift += 7;
+        pos += 1;
+        if (byte & 0x80) == 0 {
+            break
+        }
+    }
+
+    *offset = pos;
+    Some(res)
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+/// Append `slots` values of `bit_width` bits, least significant bit first. Slots past the end
+/// of `values` are zero and the output is padded to whole bytes.
+fn write_bits(values: &[u64], slots: usize, bit_width: u32, out: &mut Vec<u8>) {
+    let start = out.len();
+    out.resize(start + (slots * bit_width as usize).div_ceil(8), 0);
+    let mut bit_pos = start * 8;
+    for &value in values {
+        let mut value = value;
+        let mut left = bit_width;
+        while left > 0 {
+            let offset = (bit_pos % 8) as u32;
+            let n = (8 - offset).min(left);
+            out[bit_pos / 8] |= ((value & ((1 << n) - 1)) as u8) << offset;
+            value >>= n;
+            left -= n;
+            bit_pos += n as usize;
+        }
+    }
+}
+
+/// Read `bit_width` bits, up to 64, starting at `bit_pos`, least significant bit first
+fn read_bits_u64(data: &[u8], bit_pos: usize, bit_width: u32) -> Option<u64> {
+    if bit_pos + bit_width as usize > data.len() * 8 { return None }
+
+    let mut res = 0_u64;
+    let mut done = 0;
+    let mut pos = bit_pos;
+    while done < bit_width {
+        let offset = (pos % 8) as u32;
+        let n = (8 - offset).min(bit_width - done);
+        res |= ((data[pos / 8] >> offset) as u64 & ((1 << n) - 1)) << done;
+        done += n;
+        pos += n as usize;
+    }
+    Some(res)
+}
+
+//
+// Delta encodings
+//
+
+const DELTA_BLOCK_SIZE: usize = 128;
+const DELTA_MINIBLOCKS: usize = 4;
+
+/// DELTA_BINARY_PACKED encoding of INT32 or INT64 `values`, appending to `out`.
+/// Blocks of 128 deltas have 4 miniblocks, each packed with the bit width of its largest delta.
+/// Deltas of INT32 values wrap around at 32 bits, so they never need more than 32 bits.
+pub fn encode_delta_binary_packed(type_: Type, values: &[i64], out: &mut Vec<u8>) -> Result<(), String> {
+    let int32 = match type_ {
+        Type::INT32 => true,
+        Type::INT64 => false,
+        _ => return Err(format!("DELTA_BINARY_PACKED encoding is not supported for {:?} values", type_)),
+    };
+
+    write_leb128(DELTA_BLOCK_SIZE as u64, out);
+    write_leb128(DELTA_MINIBLOCKS as u64, out);
+    write_leb128(values.len() as u64, out);
+    write_leb128(zigzag(values.first().cloned().unwrap_or(0)), out);
+
+    let deltas: Vec<i64> = values.windows(2).map(|pair| {
+        let delta = pair[1].wrapping_sub(pair[0]);
+        if int32 { delta as i32 as i64 } else { delta }
+    }).collect();
+    let miniblock_size = DELTA_BLOCK_SIZE / DELTA_MINIBLOCKS;
+
+    for block in deltas.chunks(DELTA_BLOCK_SIZE) {
+        let min_delta = *block.iter().min().unwrap();
+        write_leb128(zigzag(min_delta), out);
+
+        let adjusted: Vec<u64> = block.iter().map(|&d| d.wrapping_sub(min_delta) as u64).collect();
+        let miniblocks: Vec<&[u64]> = adjusted.chunks(miniblock_size).collect();
+        let widths: Vec<u32> = miniblocks.iter().
+            map(|m| 64 - m.iter().max().unwrap().leading_zeros()).collect();
+        // unused miniblocks of the last block still have a bit width, but no data
+        for i in 0..DELTA_MINIBLOCKS {
+            out.push(widths.get(i).cloned().unwrap_or(0) as u8);
+        }
+        for (miniblock, &width) in miniblocks.iter().zip(widths.iter()) {
+            write_bits(miniblock, miniblock_size, width, out);
+        }
+    }
+    Ok(())
+}
+
+/// Decode DELTA_BINARY_PACKED INT32 or INT64 values. Returns values and number of bytes consumed.
+pub fn decode_delta_binary_packed(type_: Type, data: &[u8]) -> Result<(Vec<i64>, usize), String> {
+    let int32 = match type_ {
+        Type::INT32 => true,
+        Type::INT64 => false,
+        _ => return Err(format!("DELTA_BINARY_PACKED encoding is not supported for {:?} values", type_)),
+    };
+
+    let mut pos = 0_usize;
+    let mut header = || read_leb128_u64(data, &mut pos).
+        ok_or_else(|| "Unexpected end of DELTA_BINARY_PACKED header".to_string());
+    let block_size = header()? as usize;
+    let miniblocks = header()? as usize;
+    let total = header()? as usize;
+    let first = unzigzag(header()?);
+    if block_size == 0 || block_size % 128 != 0 || miniblocks == 0 || block_size % miniblocks != 0 ||
+        (block_size / miniblocks) % 32 != 0 {
+        return Err(format!("Invalid DELTA_BINARY_PACKED block of {} values in {} miniblocks", block_size, miniblocks))
+    }
+    let miniblock_size = block_size / miniblocks;
+
+    let mut values = Vec::with_capacity(total.min(data.len() * 8));
+    if total > 0 { values.push(first) }
+    let mut last = first;
+    while values.len() < total {
+        let min_delta = unzigzag(read_leb128_u64(data, &mut pos).
+            ok_or_else(|| "Unexpected end of DELTA_BINARY_PACKED block header".to_string())?);
+        if pos + miniblocks > data.len() {
+            return Err("Unexpected end of DELTA_BINARY_PACKED miniblock bit widths".to_string())
+        }
+        let widths = &data[pos .. pos + miniblocks];
+        pos += miniblocks;
+
+        for &width in widths {
+            if values.len() >= total { break }
+            let width = width as u32;
+            if width > 64 { return Err(format!("Invalid DELTA_BINARY_PACKED bit width {}", width)) }
+            let len = miniblock_size * width as usize / 8;
+            if pos + len > data.len() {
+                return Err(format!("Unexpected end of DELTA_BINARY_PACKED miniblock: {} bytes but {} left", len, data.len() - pos))
+            }
+            for i in 0 .. miniblock_size.min(total - values.len()) {
+                let delta = read_bits_u64(&data[pos .. pos + len], i * width as usize, width).unwrap() as i64;
+                last = last.wrapping_add(min_delta).wrapping_add(delta);
+                if int32 { last = last as i32 as i64 }
+                values.push(last);
+            }
+            pos += len;
+        }
+    }
+
+    Ok((values, pos))
+}
+
+/// Length of a byte array as an INT32 of DELTA_* encodings
+fn int32_length(len: usize) -> Result<i64, String> {
+    if len > i32::MAX as usize {
+        return Err(format!("Byte array of {} bytes is too long for DELTA_* encodings", len))
+    }
+    Ok(len as i64)
+}
+
+/// DELTA_LENGTH_BYTE_ARRAY encoding: DELTA_BINARY_PACKED lengths followed by concatenated bytes
+pub fn encode_delta_length_byte_array<B: AsRef<[u8]>>(values: &[B], out: &mut Vec<u8>) -> Result<(), String> {
+    let lengths = values.iter().map(|v| int32_length(v.as_ref().len())).collect::<Result<Vec<i64>, String>>()?;
+    encode_delta_binary_packed(Type::INT32, &lengths, out)?;
+    for value in values {
+        out.extend_from_slice(value.as_ref());
+    }
+    Ok(())
+}
+
+/// Decode `count` DELTA_LENGTH_BYTE_ARRAY values. Returns values and number of bytes consumed.
+pub fn decode_delta_length_byte_array(data: &[u8], count: usize) -> Result<(Vec<Vec<u8>>, usize), String> {
+    let (lengths, mut pos) = decode_delta_binary_packed(Type::INT32, data)?;
+    if lengths.len() < count {
+        return Err(format!("Expected {} DELTA_LENGTH_BYTE_ARRAY values but got {} lengths", count, lengths.len()))
+    }
+
+    let mut values = Vec::with_capacity(count);
+    for &len in &lengths[..count] {
+        if len < 0 || pos + len as usize > data.len() {
+            return Err(format!("Unexpected end of DELTA_LENGTH_BYTE_ARRAY data: byte array of {} bytes but {} left",
+                len, data.len() - pos))
+        }
+        values.push(data[pos .. pos + len as usize].to_vec());
+        pos += len as usize;
+    }
+    Ok((values, pos))
+}
+
+/// DELTA_BYTE_ARRAY encoding: DELTA_BINARY_PACKED lengths of prefixes shared with the previous
+/// value followed by DELTA_LENGTH_BYTE_ARRAY suffixes
+pub fn encode_delta_byte_array<B: AsRef<[u8]>>(values: &[B], out: &mut Vec<u8>) -> Result<(), String> {
+    let mut prefixes = Vec::with_capacity(values.len());
+    let mut suffixes = Vec::with_capacity(values.len());
+    let mut previous: &[u8] = &[];
+    for value in values {
+        let value = value.as_ref();
+        let prefix = previous.iter().zip(value.iter()).take_while(|(a, b)| a == b).count();
+        prefixes.push(int32_length(prefix)?);
+        suffixes.push(&value[prefix..]);
+        previous = value;
+    }
+    encode_delta_binary_packed(Type::INT32, &prefixes, out)?;
+    encode_delta_length_byte_array(&suffixes, out)
+}
+
+/// Decode `count` DELTA_BYTE_ARRAY values. Returns values and number of bytes consumed.
+pub fn decode_delta_byte_array(data: &[u8], count: usize) -> Result<(Vec<Vec<u8>>, usize), String> {
+    let (prefixes, pos) = decode_delta_binary_packed(Type::INT32, data)?;
+    if prefixes.len() < count {
+        return Err(format!("Expected {} DELTA_BYTE_ARRAY values but got {} prefix lengths", count, prefixes.len()))
+    }
+    let (suffixes, len) = decode_delta_length_byte_array(&data[pos..], count)?;
+
+    let mut values: Vec<Vec<u8>> = Vec::with_capacity(count);
+    for (&prefix, suffix) in prefixes.iter().zip(suffixes) {
+        let mut value = {
+            let previous = values.last().map_or(&[][..], |v| &v[..]);
+            if prefix < 0 || prefix as usize > previous.len() {
+                return Err(format!("DELTA_BYTE_ARRAY prefix of {} bytes but previous value has {}", prefix, previous.len()))
+            }
+            previous[.. prefix as usize].to_vec()
+        };
+        value.extend(suffix);
+        values.push(value);
+    }
+    Ok((values, pos + len))
+}
+
+//
+// Encoding of values
+//
+
+/// Whether `encode_values` supports values of `type_` with `encoding`
+pub fn can_encode(encoding: Encoding, type_: Type) -> bool {
+    matches!((encoding, type_),
+        (Encoding::PLAIN, _) |
+        (Encoding::RLE, Type::BOOLEAN) |
+        (Encoding::DELTA_BINARY_PACKED, Type::INT32) | (Encoding::DELTA_BINARY_PACKED, Type::INT64) |
+        (Encoding::DELTA_LENGTH_BYTE_ARRAY, Type::BYTE_ARRAY) |
+        (Encoding::DELTA_BYTE_ARRAY, Type::BYTE_ARRAY) | (Encoding::DELTA_BYTE_ARRAY, Type::FIXED_LEN_BYTE_ARRAY))
+}
+
+/// Encode values of a column of `type_` with `encoding`, appending to `out`.
+/// Dictionary encodings are not handled here, their indices need the dictionary.
+pub fn encode_values(encoding: Encoding, type_: Type, type_length: i32, values: &[Value], out: &mut Vec<u8>) -> Result<(), String> {
+    if !can_encode(encoding, type_) {
+        return Err(format!("{:?} encoding is not supported for {:?} values", encoding, type_))
+    }
+    let mismatch = |value: &Value| format!("Value {:?} does not match column type {:?} (length {})", value, type_, type_length);
+
+    match encoding {
+        Encoding::PLAIN => encode_plain(type_, type_length, values, out),
+        Encoding::RLE => {
+            let bits = values.iter().map(|v| match *v {
+                Value::Boolean(b) => Ok(b as u32),
+                _ => Err(mismatch(v)),
+            }).collect::<Result<Vec<u32>, String>>()?;
+            let encoded = encode_rle(&bits, 1);
+            let mut len = [0_u8; 4];
+            LittleEndian::write_u32(&mut len, encoded.len() as u32);
+            out.extend_from_slice(&len);
+            out.extend(encoded);
+            Ok(())
+        },
+        Encoding::DELTA_BINARY_PACKED => {
+            let ints = values.iter().map(|v| match (type_, v) {
+                (Type::INT32, &Value::Int32(i)) => Ok(i as i64),
+                (Type::INT64, &Value::Int64(i)) => Ok(i),
+                _ => Err(mismatch(v)),
+            }).collect::<Result<Vec<i64>, String>>()?;
+            encode_delta_binary_packed(type_, &ints, out)
+        },
+        _ => {
+            let bytes = values.iter().map(|v| match *v {
+                Value::Bytes(ref b) if type_ == Type::BYTE_ARRAY || b.len() == type_length as usize => Ok(&b[..]),
+                _ => Err(mismatch(v)),
+            }).collect::<Result<Vec<&[u8]>, String>>()?;
+            if encoding == Encoding::DELTA_LENGTH_BYTE_ARRAY {
+                encode_delta_length_byte_array(&bytes, out)
+            } else {
+                encode_delta_byte_array(&bytes, out)
+            }
+        },
+    }
+}
+
+/// Decode `count` values of a column of `type_` encoded with any encoding `encode_values` supports
+pub fn decode_values(encoding: Encoding, type_: Type, type_length: i32, data: &[u8], count: usize) -> Result<Vec<Value>, String> {
+    if !can_encode(encoding, type_) {
+        return Err(format!("{:?} encoding is not supported for {:?} values", encoding, type_))
+    }
+
+    match encoding {
+        Encoding::PLAIN => decode_plain(type_, type_length, data, count).map(|(values, _)| values),
+        Encoding::RLE => {
+            let values: Vec<Value> = BitPackingRleReader::new(1, data)?.into_iter().take(count).
+                map(|b| Value::Boolean(b == 1)).collect();
+            if values.len() < count {
+                return Err(format!("Expected {} RLE encoded booleans but got {}", count, values.len()))
+            }
+            Ok(values)
+        },
+        Encoding::DELTA_BINARY_PACKED => {
+            let (ints, _) = decode_delta_binary_packed(type_, data)?;
+            if ints.len() < count {
+                return Err(format!("Expected {} DELTA_BINARY_PACKED values but got {}", count, ints.len()))
+            }
+            Ok(ints.into_iter().take(count).
+                map(|i| if type_ == Type::INT32 { Value::Int32(i as i32) } else { Value::Int64(i) }).collect())
+        },
+        _ => {
+            let (bytes, _) = if encoding == Encoding::DELTA_LENGTH_BYTE_ARRAY {
+                decode_delta_length_byte_array(data, count)?
+            } else {
+                decode_delta_byte_array(data, count)?
+            };
+            Ok(bytes.into_iter().map(Value::Bytes).collect())
+        },
+    }
+}
+
+/// Decode RLE_DICTIONARY/PLAIN_DICTIONARY indices: bit width byte followed by RLE/bit-packed hybrid data.
+pub fn decode_dictionary_indices(data: &[u8], count: usize) -> Result<Vec<usize>,String> {
+    if data.is_empty() {
+        return if count == 0 { Ok(vec![]) } else { Err("Dictionary indices are empty".to_string()) }
+    }
+
+    let bit_width = data[0] as u32;
+    if bit_width > 32 { return Err(format!("Invalid dictionary index bit width {}", bit_width)) }
+    let indices: Vec<usize> = RleIter::new(&data[1..], bit_width).take(count).map(|i| i as usize).collect();
+    if indices.len() < count {
+        return Err(format!("Expected {} dictionary indices but got {}", count, indices.len()))
+    }
+    Ok(indices)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +743,128 @@ mod tests {
         assert_eq!(1, bit_width(1));
         assert_eq!(4, bit_width(9));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rle_hybrid() {
+        // RLE run of 3 x 5, then one bit-packed group of 8 values 0..7 with bit width 3
+        let data = [0b110, 5, 0b11, 0b10001000, 0b11000110, 0b11111010];
+        let values: Vec<i32> = RleIter::new(&data, 3).collect();
+        assert_eq!(vec![5, 5, 5, 0, 1, 2, 3, 4, 5, 6, 7], values);
+    }
+
+    #[test]
+    fn plain_values() {
+        let data = [3, 0, 0, 0, b'a', b'b', b'c', 0, 0, 0, 0];
+        let (values, len) = decode_plain(Type::BYTE_ARRAY, 0, &data, 2).unwrap();
+        assert_eq!(vec![Value::Bytes(b"abc".to_vec()), Value::Bytes(vec![])], values);
+        assert_eq!(data.len(), len);
+
+        let (values, _) = decode_plain(Type::BOOLEAN, 0, &[0b101], 3).unwrap();
+        assert_eq!(vec![Value::Boolean(true), Value::Boolean(false), Value::Boolean(true)], values);
+        assert!(decode_plain(Type::INT64, 0, &[0; 12], 2).is_err());
+    }
+
+    #[test]
+    fn plain_roundtrip() {
+        let values = vec![Value::Bytes(b"abc".to_vec()), Value::Bytes(vec![])];
+        let mut data = vec![];
+        encode_plain(Type::BYTE_ARRAY, 0, &values, &mut data).unwrap();
+        assert_eq!((values, data.len()), decode_plain(Type::BYTE_ARRAY, 0, &data, 2).unwrap());
+
+        let mut data = vec![];
+        encode_plain(Type::BOOLEAN, 0, &[Value::Boolean(true), Value::Boolean(false), Value::Boolean(true)], &mut data).unwrap();
+        assert_eq!(vec![0b101], data);
+        assert!(encode_plain(Type::INT32, 0, &[Value::Int64(1)], &mut vec![]).is_err());
+        assert!(encode_plain(Type::FIXED_LEN_BYTE_ARRAY, 2, &[Value::Bytes(vec![1])], &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn rle_roundtrip() {
+        let values = vec![1, 1, 1, 0, 5, 5, 300];
+        let data = encode_rle(&values, 9);
+        assert_eq!(values, RleIter::new(&data, 9).take(values.len()).map(|v| v as u32).collect::<Vec<_>>());
+
+        // one bit-packed group padded with zeros
+        assert_eq!(vec![0b11, 0b111001, 0], encode_rle(&[1, 2, 3], 2));
+        // 3 literals, group completed by the run, RLE run of the other 10
+        let values: Vec<u32> = vec![0, 1, 0].into_iter().chain(vec![1; 15]).collect();
+        let data = encode_rle(&values, 1);
+        assert_eq!(vec![0b11, 0b11111010, 10 << 1, 1], data);
+        assert_eq!(values, RleIter::new(&data, 1).take(values.len()).map(|v| v as u32).collect::<Vec<_>>());
+
+        // long bit-packed sequences are split into runs of 63 groups
+        let values: Vec<u32> = (0..1000).map(|i| i % 7).collect();
+        let data = encode_rle(&values, 3);
+        assert_eq!(values, RleIter::new(&data, 3).take(values.len()).map(|v| v as u32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn delta_binary_packed() {
+        let values: Vec<i64> = (0..300).map(|i| i * 3 + (i % 5)).chain(vec![i64::MIN, i64::MAX, 0]).collect();
+        let mut data = vec![];
+        encode_delta_binary_packed(Type::INT64, &values, &mut data).unwrap();
+        assert_eq!((values.clone(), data.len()), decode_delta_binary_packed(Type::INT64, &data).unwrap());
+
+        // header: blocks of 128 in 4 miniblocks, 3 values, first 7 (zigzag 14), then min delta -1
+        // (zigzag 1) and deltas [1, -1] adjusted to [2, 0], packed with 2 bits in the first miniblock
+        let mut data = vec![];
+        encode_delta_binary_packed(Type::INT32, &[7, 8, 7], &mut data).unwrap();
+        assert_eq!(vec![128, 1, 4, 3, 14, 1, 2, 0, 0, 0, 0b0010, 0, 0, 0, 0, 0, 0, 0], data);
+        assert_eq!(vec![7, 8, 7], decode_delta_binary_packed(Type::INT32, &data).unwrap().0);
+
+        // INT32 deltas wrap around at 32 bits
+        let values = vec![i32::MIN as i64, i32::MAX as i64, 0];
+        let mut data = vec![];
+        encode_delta_binary_packed(Type::INT32, &values, &mut data).unwrap();
+        // header, min delta, bit widths and a miniblock of 32 bit values at most
+        assert!(data.len() <= 9 + 5 + 4 + 32 * 4);
+        assert_eq!(values, decode_delta_binary_packed(Type::INT32, &data).unwrap().0);
+
+        let mut data = vec![];
+        encode_delta_binary_packed(Type::INT64, &[], &mut data).unwrap();
+        assert_eq!((vec![], data.len()), decode_delta_binary_packed(Type::INT64, &data).unwrap());
+        assert!(decode_delta_binary_packed(Type::INT64, &data[..data.len() - 1]).is_err());
+        assert!(encode_delta_binary_packed(Type::DOUBLE, &[1], &mut vec![]).is_err());
+    }
+
+    #[test]
+    fn delta_byte_arrays() {
+        let values: Vec<&[u8]> = vec![b"apple", b"applied", b"", b"banana", b"band"];
+        let owned: Vec<Vec<u8>> = values.iter().map(|v| v.to_vec()).collect();
+
+        let mut data = vec![];
+        encode_delta_length_byte_array(&values, &mut data).unwrap();
+        assert_eq!((owned.clone(), data.len()), decode_delta_length_byte_array(&data, values.len()).unwrap());
+        assert!(decode_delta_length_byte_array(&data, values.len() + 1).is_err());
+
+        let mut data = vec![];
+        encode_delta_byte_array(&values, &mut data).unwrap();
+        assert_eq!((owned, data.len()), decode_delta_byte_array(&data, values.len()).unwrap());
+        let (prefixes, _) = decode_delta_binary_packed(Type::INT32, &data).unwrap();
+        assert_eq!(vec![0, 4, 0, 0, 3], prefixes);
+
+        // lengths are INT32
+        assert_eq!(Ok(i32::MAX as i64), int32_length(i32::MAX as usize));
+        assert!(int32_length(i32::MAX as usize + 1).is_err());
+    }
+
+    #[test]
+    fn values_roundtrip() {
+        let cases = vec![
+            (Encoding::RLE, Type::BOOLEAN, 0, vec![Value::Boolean(true), Value::Boolean(false), Value::Boolean(true)]),
+            (Encoding::DELTA_BINARY_PACKED, Type::INT32, 0, vec![Value::Int32(-1), Value::Int32(5)]),
+            (Encoding::DELTA_LENGTH_BYTE_ARRAY, Type::BYTE_ARRAY, 0, vec![Value::Bytes(b"ab".to_vec()), Value::Bytes(vec![])]),
+            (Encoding::DELTA_BYTE_ARRAY, Type::FIXED_LEN_BYTE_ARRAY, 2, vec![Value::Bytes(b"ab".to_vec()), Value::Bytes(b"ac".to_vec())]),
+        ];
+        for (encoding, type_, type_length, values) in cases {
+            let mut data = vec![];
+            encode_values(encoding, type_, type_length, &values, &mut data).unwrap();
+            assert_eq!(values, decode_values(encoding, type_, type_length, &data, values.len()).unwrap());
+        }
+
+        assert!(!can_encode(Encoding::DELTA_BINARY_PACKED, Type::DOUBLE));
+        assert!(encode_values(Encoding::DELTA_BINARY_PACKED, Type::INT64, 0, &[Value::Int32(1)], &mut vec![]).is_err());
+        assert!(encode_values(Encoding::DELTA_BYTE_ARRAY, Type::FIXED_LEN_BYTE_ARRAY, 2, &[Value::Bytes(vec![1])], &mut vec![]).is_err());
+        assert!(decode_values(Encoding::RLE_DICTIONARY, Type::INT64, 0, &[], 0).is_err());
+    }
+}
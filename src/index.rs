@@ -0,0 +1,249 @@
+use std::cmp::Ordering;
+use std::io::{Read, Seek, SeekFrom, Result, Error, ErrorKind};
+use std::ops::{Bound, Range};
+use thrift::protocol::TCompactInputProtocol;
+use parquet::*;
+use value::{Value, SortOrder, sort_order, comparable};
+
+/// Read page `ColumnIndex` of a column chunk, if the file has one
+pub fn read_column_index<R: Read + Seek>(file: &mut R, chunk: &ColumnChunk) -> Result<Option<ColumnIndex>> {
+    let (offset, len) = match (chunk.column_index_offset, chunk.column_index_length) {
+        (Some(offset), Some(len)) => (offset, len),
+        _ => return Ok(None),
+    };
+
+    file.seek(SeekFrom::Start(offset as u64))?;
+    let mut protocol = TCompactInputProtocol::new(file.take(len as u64));
+    let index = ColumnIndex::read_from_in_protocol(&mut protocol).
+        map_err(|e| Error::new(ErrorKind::InvalidData, format!("Failed to deserialize ColumnIndex: {}", e)))?;
+    Ok(Some(index))
+}
+
+/// Read page `OffsetIndex` of a column chunk, if the file has one
+pub fn read_offset_index<R: Read + Seek>(file: &mut R, chunk: &ColumnChunk) -> Result<Option<OffsetIndex>> {
+    let (offset, len) = match (chunk.offset_index_offset, chunk.offset_index_length) {
+        (Some(offset), Some(len)) => (offset, len),
+        _ => return Ok(None),
+    };
+
+    file.seek(SeekFrom::Start(offset as u64))?;
+    let mut protocol = TCompactInputProtocol::new(file.take(len as u64));
+    let index = OffsetIndex::read_from_in_protocol(&mut protocol).
+        map_err(|e| Error::new(ErrorKind::InvalidData, format!("Failed to deserialize OffsetIndex: {}", e)))?;
+    Ok(Some(index))
+}
+
+/// Rows covered by a page, `num_rows` is the row count of the row group.
+pub fn page_rows(offset_index: &OffsetIndex, page: usize, num_rows: i64) -> Range<i64> {
+    let locations = &offset_index.page_locations;
+    let end = locations.get(page + 1).map_or(num_rows, |l| l.first_row_index);
+    locations[page].first_row_index .. end
+}
+
+/// Decoded page min/max values of a column chunk, searchable by value.
+/// When bounds are ordered, lookups are binary searches, otherwise pages are scanned.
+#[derive(Debug)]
+pub struct PageBounds {
+    /// Ordinals of pages which have at least one non-null value
+    pages: Vec<usize>,
+    min_values: Vec<Value>,
+    max_values: Vec<Value>,
+    order: BoundaryOrder,
+    sort_order: SortOrder,
+}
+
+impl PageBounds {
+    /// Decode bounds of the leaf column described by `element`, which defines their type and order
+    pub fn new(index: &ColumnIndex, element: &SchemaElement) -> std::result::Result<PageBounds, String> {
+        let type_ = element.type_.ok_or_else(|| format!("Column '{}' is not a leaf", element.name))?;
+        let sort_order = sort_order(element);
+        let page_count = index.null_pages.len();
+        if index.min_values.len() != page_count || index.max_values.len() != page_count {
+            return Err(format!("ColumnIndex lists have different lengths: null_pages: {}, min_values: {}, max_values: {}",
+                page_count, index.min_values.len(), index.max_values.len()));
+        }
+
+        let mut pages = Vec::new();
+        let mut min_values = Vec::new();
+        let mut max_values = Vec::new();
+        for page in 0..page_count {
+            if index.null_pages[page] { continue }
+            pages.push(page);
+            min_values.push(comparable(element, Value::from_plain(type_, &index.min_values[page])?)?);
+            max_values.push(comparable(element, Value::from_plain(type_, &index.max_values[page])?)?);
+        }
+
+        let order = if sort_order == SortOrder::Undefined { BoundaryOrder::UNORDERED } else { index.boundary_order };
+        Ok(PageBounds { pages, min_values, max_values, order, sort_order })
+    }
+
+    /// If the index does not declare an order, but the row group is sorted by this column
+    /// (it is the first of `sorting_columns`), pages are ordered too.
+    pub fn with_sorting_columns(mut self, row_group: &RowGroup, column_idx: usize) -> PageBounds {
+        if self.order != BoundaryOrder::UNORDERED || self.sort_order == SortOrder::Undefined {
+            return self
+        }
+
+        // Only the first sorting column is sorted across the whole row group
+        if let Some(sorting) = row_group.sorting_columns.as_ref().and_then(|s| s.first()) {
+            if sorting.column_idx as usize == column_idx {
+                self.order = if sorting.descending { BoundaryOrder::DESCENDING } else { BoundaryOrder::ASCENDING };
+            }
+        }
+        self
+    }
+
+    pub fn boundary_order(&self) -> BoundaryOrder {
+        self.order
+    }
+
+    /// Pages which may contain `value`
+    pub fn find_eq(&self, value: &Value) -> Vec<usize> {
+        self.find_range(Bound::Included(value), Bound::Included(value))
+    }
+
+    /// Pages which may contain values within range, in page order
+    pub fn find_range(&self, lower: Bound<&Value>, upper: Bound<&Value>) -> Vec<usize> {
+        let sort_order = self.sort_order;
+        // Page is entirely below lower bound
+        let below = |i: usize| match lower {
+            Bound::Included(v) => self.max_values[i].compare(v, sort_order) == Some(Ordering::Less),
+            Bound::Excluded(v) => self.max_values[i].compare(v, sort_order).is_some_and(|o| o != Ordering::Greater),
+            Bound::Unbounded => false,
+        };
+        // Page is entirely above upper bound
+        let above = |i: usize| match upper {
+            Bound::Included(v) => self.min_values[i].compare(v, sort_order) == Some(Ordering::Greater),
+            Bound::Excluded(v) => self.min_values[i].compare(v, sort_order).is_some_and(|o| o != Ordering::Less),
+            Bound::Unbounded => false,
+        };
+
+        let len = self.pages.len();
+        let range = match self.order {
+            BoundaryOrder::ASCENDING => partition_point(len, below) .. partition_point(len, |i| !above(i)),
+            BoundaryOrder::DESCENDING => partition_point(len, above) .. partition_point(len, |i| !below(i)),
+            BoundaryOrder::UNORDERED => {
+                return (0..len).filter(|&i| !below(i) && !above(i)).map(|i| self.pages[i]).collect()
+            }
+        };
+
+        if range.start >= range.end { return vec![] }
+        self.pages[range].to_vec()
+    }
+}
+
+/// Lower bound of a byte array min value of at most `len` bytes, in unsigned byte order
+pub fn truncate_min(value: &[u8], len: usize) -> Vec<u8> {
+    value[.. value.len().min(len)].to_vec()
+}
+
+/// Upper bound of a byte array max value of at most `len` bytes, in unsigned byte order:
+/// the prefix with its last byte incremented. The value is kept whole when there is no
+/// such prefix, i.e. its first `len` bytes are all 0xff.
+pub fn truncate_max(value: &[u8], len: usize) -> Vec<u8> {
+    if value.len() <= len { return value.to_vec() }
+
+    let mut prefix = value[..len].to_vec();
+    while let Some(last) = prefix.pop() {
+        if last != 0xff {
+            prefix.push(last + 1);
+            return prefix
+        }
+    }
+    value.to_vec()
+}
+
+/// Index of the first element for which `pred` is false, assuming `pred` is true for a prefix.
+fn partition_point<F: Fn(usize) -> bool>(len: usize, pred: F) -> usize {
+    let (mut lo, mut hi) = (0, len);
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if pred(mid) { lo = mid + 1 } else { hi = mid }
+    }
+    lo
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(bounds: &[Option<(i64, i64)>], order: BoundaryOrder) -> ColumnIndex {
+        let plain = |v: i64| Value::Int64(v).to_plain();
+        ColumnIndex::new(
+            bounds.iter().map(|b| b.is_none()).collect(),
+            bounds.iter().map(|b| b.map_or(vec![], |(min, _)| plain(min))).collect(),
+            bounds.iter().map(|b| b.map_or(vec![], |(_, max)| plain(max))).collect(),
+            order,
+            None)
+    }
+
+    fn int64() -> SchemaElement {
+        SchemaElement::new(Type::INT64, None, FieldRepetitionType::OPTIONAL, "v".to_string(), None, None, None, None, None, None)
+    }
+
+    #[test]
+    fn ascending_lookup() {
+        let index = index(&[Some((1, 10)), Some((10, 20)), None, Some((21, 30)), Some((31, 40))], BoundaryOrder::ASCENDING);
+        let bounds = PageBounds::new(&index, &int64()).unwrap();
+
+        assert_eq!(vec![0, 1], bounds.find_eq(&Value::Int64(10)));
+        assert_eq!(vec![3], bounds.find_eq(&Value::Int64(25)));
+        assert!(bounds.find_eq(&Value::Int64(41)).is_empty());
+        assert_eq!(vec![1, 3], bounds.find_range(Bound::Excluded(&Value::Int64(10)), Bound::Included(&Value::Int64(21))));
+        assert_eq!(vec![3, 4], bounds.find_range(Bound::Included(&Value::Int64(21)), Bound::Unbounded));
+    }
+
+    #[test]
+    fn descending_lookup() {
+        let index = index(&[Some((31, 40)), Some((21, 30)), Some((10, 20)), Some((1, 10))], BoundaryOrder::DESCENDING);
+        let bounds = PageBounds::new(&index, &int64()).unwrap();
+
+        assert_eq!(vec![2, 3], bounds.find_eq(&Value::Int64(10)));
+        assert_eq!(vec![0, 1], bounds.find_range(Bound::Included(&Value::Int64(25)), Bound::Unbounded));
+        assert!(bounds.find_range(Bound::Unbounded, Bound::Excluded(&Value::Int64(1))).is_empty());
+    }
+
+    #[test]
+    fn sorting_columns_imply_order() {
+        let index = index(&[Some((1, 5)), Some((5, 9)), Some((9, 12))], BoundaryOrder::UNORDERED);
+        let row_group = RowGroup::new(vec![], 0, 100, vec![SortingColumn::new(1, false, true)]);
+
+        let bounds = PageBounds::new(&index, &int64()).unwrap();
+        assert_eq!(BoundaryOrder::UNORDERED, bounds.with_sorting_columns(&row_group, 0).boundary_order());
+
+        let bounds = PageBounds::new(&index, &int64()).unwrap().
+            with_sorting_columns(&row_group, 1);
+        assert_eq!(BoundaryOrder::ASCENDING, bounds.boundary_order());
+        assert_eq!(vec![0, 1], bounds.find_eq(&Value::Int64(5)));
+    }
+
+    #[test]
+    fn float16_bounds() {
+        let element = SchemaElement::new(Type::FIXED_LEN_BYTE_ARRAY, 2, FieldRepetitionType::OPTIONAL, "f".to_string(), None,
+            None, None, None, None, LogicalType::FLOAT16(Float16Type::new()));
+        // pages [-2.0, -1.0] and [0.5, 1.0]
+        let index = ColumnIndex::new(vec![false, false], vec![vec![0x00, 0xc0], vec![0x00, 0x38]],
+            vec![vec![0x00, 0xbc], vec![0x00, 0x3c]], BoundaryOrder::ASCENDING, None);
+        let bounds = PageBounds::new(&index, &element).unwrap();
+
+        assert_eq!(vec![0], bounds.find_eq(&Value::Float(-1.5)));
+        assert_eq!(vec![1], bounds.find_range(Bound::Included(&Value::Float(0.0)), Bound::Unbounded));
+    }
+
+    #[test]
+    fn truncation() {
+        assert_eq!(b"abc".to_vec(), truncate_min(b"abcdef", 3));
+        assert_eq!(b"ab".to_vec(), truncate_min(b"ab", 3));
+        assert_eq!(b"abd".to_vec(), truncate_max(b"abcdef", 3));
+        assert_eq!(vec![b'b'], truncate_max(&[b'a', 0xff, 0xff, 0x01], 3));
+        assert_eq!(vec![0xff, 0xff, 0x01], truncate_max(&[0xff, 0xff, 0x01], 2));
+        assert_eq!(b"ab".to_vec(), truncate_max(b"ab", 3));
+    }
+
+    #[test]
+    fn rows_of_page() {
+        let offsets = OffsetIndex::new(vec![PageLocation::new(4, 100, 0), PageLocation::new(104, 100, 50)]);
+        assert_eq!(0..50, page_rows(&offsets, 0, 80));
+        assert_eq!(50..80, page_rows(&offsets, 1, 80));
+    }
+}
@@ -3,10 +3,29 @@ extern crate byteorder;
 extern crate thrift;
 extern crate ordered_float;
 extern crate try_from;
-
-mod parquet;
-mod encodings;
+#[cfg(feature = "rust_decimal")]
+extern crate rust_decimal;
+#[cfg(feature = "chrono")]
+extern crate chrono;
+#[cfg(feature = "serde_json")]
+extern crate serde_json;
+
+pub mod parquet;
+pub mod encodings;
 mod levels;
+pub mod schema;
+pub mod value;
+pub mod index;
+pub mod compression;
+pub mod reader;
+pub mod aggregate;
+pub mod decimal;
+pub mod temporal;
+pub mod logical;
+pub mod record;
+pub mod column_writer;
+pub mod properties;
+pub mod writer;
 
 use std::fs::{OpenOptions, File};
 use std::io;
@@ -0,0 +1,241 @@
+use std::fmt;
+use parquet::*;
+use temporal::Unit;
+
+/// Effective logical type of a schema element. Files written with `LogicalType`
+/// and with legacy `ConvertedType` annotations resolve to the same model.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Logical {
+    String,
+    Map,
+    /// Legacy annotation of the repeated key-value group of a map
+    MapKeyValue,
+    List,
+    Enum,
+    Decimal { precision: i32, scale: i32 },
+    Date,
+    Time { unit: Unit, adjusted_to_utc: bool },
+    Timestamp { unit: Unit, adjusted_to_utc: bool },
+    Integer { bit_width: i8, signed: bool },
+    /// Always null column
+    Unknown,
+    Json,
+    Bson,
+    Uuid,
+    /// Half-precision float, stored as 2 little-endian bytes
+    Float16,
+    /// Legacy months, days and milliseconds interval, has no `LogicalType` counterpart
+    Interval,
+}
+
+/// Resolve logical type of an element, preferring `logical_type` over `converted_type`.
+/// Fails when the annotation is not allowed for the element's physical type.
+pub fn resolve(element: &SchemaElement) -> Result<Option<Logical>, String> {
+    let logical = match element.logical_type {
+        Some(ref logical_type) => Some(from_logical_type(logical_type)),
+        None => match element.converted_type {
+            Some(converted_type) => Some(from_converted_type(converted_type, element)?),
+            None => None,
+        }
+    };
+
+    if let Some(logical) = logical {
+        validate(&logical, element)?;
+    }
+    Ok(logical)
+}
+
+fn from_logical_type(logical_type: &LogicalType) -> Logical {
+    let unit = |unit: &TimeUnit| match *unit {
+        TimeUnit::MILLIS(_) => Unit::Millis,
+        TimeUnit::MICROS(_) => Unit::Micros,
+        TimeUnit::NANOS(_) => Unit::Nanos,
+    };
+
+    match *logical_type {
+        LogicalType::STRING(_) => Logical::String,
+        LogicalType::MAP(_) => Logical::Map,
+        LogicalType::LIST(_) => Logical::List,
+        LogicalType::ENUM(_) => Logical::Enum,
+        LogicalType::DECIMAL(ref d) => Logical::Decimal { precision: d.precision, scale: d.scale },
+        LogicalType::DATE(_) => Logical::Date,
+        LogicalType::TIME(ref t) => Logical::Time { unit: unit(&t.unit), adjusted_to_utc: t.is_adjusted_to_u_t_c },
+        LogicalType::TIMESTAMP(ref t) => Logical::Timestamp { unit: unit(&t.unit), adjusted_to_utc: t.is_adjusted_to_u_t_c },
+        LogicalType::INTEGER(ref i) => Logical::Integer { bit_width: i.bit_width, signed: i.is_signed },
+        LogicalType::UNKNOWN(_) => Logical::Unknown,
+        LogicalType::JSON(_) => Logical::Json,
+        LogicalType::BSON(_) => Logical::Bson,
+        LogicalType::UUID(_) => Logical::Uuid,
+        LogicalType::FLOAT16(_) => Logical::Float16,
+    }
+}
+
+fn from_converted_type(converted_type: ConvertedType, element: &SchemaElement) -> Result<Logical, String> {
+    let int = |bit_width: i8, signed: bool| Logical::Integer { bit_width, signed };
+
+    Ok(match converted_type {
+        ConvertedType::UTF8 => Logical::String,
+        ConvertedType::MAP => Logical::Map,
+        ConvertedType::MAP_KEY_VALUE => Logical::MapKeyValue,
+        ConvertedType::LIST => Logical::List,
+        ConvertedType::ENUM => Logical::Enum,
+        ConvertedType::DECIMAL => {
+            let precision = element.precision.
+                ok_or_else(|| format!("DECIMAL column '{}' does not have precision", element.name))?;
+            Logical::Decimal { precision, scale: element.scale.unwrap_or(0) }
+        },
+        ConvertedType::DATE => Logical::Date,
+        // Legacy time types are adjusted to UTC
+        ConvertedType::TIME_MILLIS => Logical::Time { unit: Unit::Millis, adjusted_to_utc: true },
+        ConvertedType::TIME_MICROS => Logical::Time { unit: Unit::Micros, adjusted_to_utc: true },
+        ConvertedType::TIMESTAMP_MILLIS => Logical::Timestamp { unit: Unit::Millis, adjusted_to_utc: true },
+        ConvertedType::TIMESTAMP_MICROS => Logical::Timestamp { unit: Unit::Micros, adjusted_to_utc: true },
+        ConvertedType::UINT_8 => int(8, false),
+        ConvertedType::UINT_16 => int(16, false),
+        ConvertedType::UINT_32 => int(32, false),
+        ConvertedType::UINT_64 => int(64, false),
+        ConvertedType::INT_8 => int(8, true),
+        ConvertedType::INT_16 => int(16, true),
+        ConvertedType::INT_32 => int(32, true),
+        ConvertedType::INT_64 => int(64, true),
+        ConvertedType::JSON => Logical::Json,
+        ConvertedType::BSON => Logical::Bson,
+        ConvertedType::INTERVAL => Logical::Interval,
+    })
+}
+
+/// Check logical type is allowed for the physical type of the element
+fn validate(logical: &Logical, element: &SchemaElement) -> Result<(), String> {
+    let type_ = element.type_;
+    let fixed_len = |len: i32| type_ == Some(Type::FIXED_LEN_BYTE_ARRAY) && element.type_length == Some(len);
+
+    let valid = match *logical {
+        Logical::Map | Logical::MapKeyValue | Logical::List => type_.is_none(),
+        Logical::String | Logical::Enum | Logical::Json | Logical::Bson => type_ == Some(Type::BYTE_ARRAY),
+        Logical::Decimal { .. } =>
+            matches!(type_, Some(Type::INT32) | Some(Type::INT64) | Some(Type::BYTE_ARRAY) | Some(Type::FIXED_LEN_BYTE_ARRAY)),
+        Logical::Date => type_ == Some(Type::INT32),
+        Logical::Time { unit: Unit::Millis, .. } => type_ == Some(Type::INT32),
+        Logical::Time { .. } => type_ == Some(Type::INT64),
+        Logical::Timestamp { .. } => type_ == Some(Type::INT64),
+        Logical::Integer { bit_width: 64, .. } => type_ == Some(Type::INT64),
+        Logical::Integer { bit_width: 8, .. } | Logical::Integer { bit_width: 16, .. } |
+        Logical::Integer { bit_width: 32, .. } => type_ == Some(Type::INT32),
+        Logical::Integer { .. } => false,
+        Logical::Unknown => true,
+        Logical::Uuid => fixed_len(16),
+        Logical::Float16 => fixed_len(2),
+        Logical::Interval => fixed_len(12),
+    };
+
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Logical type {:?} is not allowed for '{}' of physical type {:?} (length {:?})",
+            logical, element.name, type_, element.type_length))
+    }
+}
+
+/// UUID value, stored as 16 raw bytes in big-endian order
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Uuid(pub [u8; 16]);
+
+impl Uuid {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Uuid, String> {
+        if bytes.len() != 16 {
+            return Err(format!("UUID must be 16 bytes but got {}", bytes.len()))
+        }
+        let mut uuid = [0_u8; 16];
+        uuid.copy_from_slice(bytes);
+        Ok(Uuid(uuid))
+    }
+}
+
+impl fmt::Display for Uuid {
+    /// Hyphenated lowercase form, e.g. 123e4567-e89b-12d3-a456-426614174000
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, b) in self.0.iter().enumerate() {
+            if i == 4 || i == 6 || i == 8 || i == 10 {
+                write!(f, "-")?;
+            }
+            write!(f, "{:02x}", b)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(type_: Option<Type>, converted_type: Option<ConvertedType>, logical_type: Option<LogicalType>) -> SchemaElement {
+        SchemaElement::new(type_, None, FieldRepetitionType::OPTIONAL, "f".to_string(), None,
+            converted_type, None, None, None, logical_type)
+    }
+
+    #[test]
+    fn logical_type_preferred() {
+        let e = element(Some(Type::INT32), Some(ConvertedType::INT_32),
+            Some(LogicalType::INTEGER(IntType::new(16, false))));
+        assert_eq!(Some(Logical::Integer { bit_width: 16, signed: false }), resolve(&e).unwrap());
+    }
+
+    #[test]
+    fn converted_types() {
+        let e = element(Some(Type::BYTE_ARRAY), Some(ConvertedType::UTF8), None);
+        assert_eq!(Some(Logical::String), resolve(&e).unwrap());
+
+        let e = element(None, Some(ConvertedType::MAP_KEY_VALUE), None);
+        assert_eq!(Some(Logical::MapKeyValue), resolve(&e).unwrap());
+
+        let e = element(Some(Type::INT64), Some(ConvertedType::UINT_64), None);
+        assert_eq!(Some(Logical::Integer { bit_width: 64, signed: false }), resolve(&e).unwrap());
+
+        let mut e = element(Some(Type::FIXED_LEN_BYTE_ARRAY), Some(ConvertedType::INTERVAL), None);
+        e.type_length = Some(12);
+        assert_eq!(Some(Logical::Interval), resolve(&e).unwrap());
+
+        let mut e = element(Some(Type::INT64), Some(ConvertedType::DECIMAL), None);
+        assert!(resolve(&e).is_err());
+        e.precision = Some(18);
+        assert_eq!(Some(Logical::Decimal { precision: 18, scale: 0 }), resolve(&e).unwrap());
+
+        assert_eq!(None, resolve(&element(Some(Type::INT64), None, None)).unwrap());
+    }
+
+    #[test]
+    fn uuid() {
+        let mut e = element(Some(Type::FIXED_LEN_BYTE_ARRAY), None, Some(LogicalType::UUID(UUIDType::new())));
+        e.type_length = Some(16);
+        assert_eq!(Some(Logical::Uuid), resolve(&e).unwrap());
+        e.type_length = Some(8);
+        assert!(resolve(&e).is_err());
+
+        let uuid = Uuid::from_bytes(&[0x12, 0x3e, 0x45, 0x67, 0xe8, 0x9b, 0x12, 0xd3, 0xa4, 0x56, 0x42, 0x66, 0x14, 0x17, 0x40, 0x00]).unwrap();
+        assert_eq!("123e4567-e89b-12d3-a456-426614174000", uuid.to_string());
+        assert!(Uuid::from_bytes(&[0; 15]).is_err());
+    }
+
+    #[test]
+    fn nanos_and_float16() {
+        let nanos = TimestampType::new(false, TimeUnit::NANOS(NanoSeconds::new()));
+        let e = element(Some(Type::INT64), None, Some(LogicalType::TIMESTAMP(nanos)));
+        assert_eq!(Some(Logical::Timestamp { unit: Unit::Nanos, adjusted_to_utc: false }), resolve(&e).unwrap());
+
+        let mut e = element(Some(Type::FIXED_LEN_BYTE_ARRAY), None, Some(LogicalType::FLOAT16(Float16Type::new())));
+        e.type_length = Some(2);
+        assert_eq!(Some(Logical::Float16), resolve(&e).unwrap());
+        e.type_length = Some(4);
+        assert!(resolve(&e).is_err());
+    }
+
+    #[test]
+    fn invalid_physical_type() {
+        assert!(resolve(&element(Some(Type::INT32), Some(ConvertedType::UTF8), None)).is_err());
+        assert!(resolve(&element(Some(Type::INT32), Some(ConvertedType::INT_64), None)).is_err());
+        assert!(resolve(&element(Some(Type::BYTE_ARRAY), Some(ConvertedType::LIST), None)).is_err());
+        assert!(resolve(&element(Some(Type::INT64), Some(ConvertedType::TIME_MILLIS), None)).is_err());
+        assert!(resolve(&element(Some(Type::INT32), None,
+            Some(LogicalType::INTEGER(IntType::new(12, true))))).is_err());
+    }
+}
@@ -0,0 +1,260 @@
+use parquet::*;
+use column_writer::{ColumnProperties, WriterVersion};
+
+/// Maximum rows of a row group when none is configured
+pub const DEFAULT_MAX_ROW_GROUP_ROWS: usize = 1024 * 1024;
+/// Maximum size of a row group when none is configured
+pub const DEFAULT_MAX_ROW_GROUP_SIZE: usize = 128 * 1024 * 1024;
+
+const CREATED_BY: &str = concat!("rust-parquet version ", env!("CARGO_PKG_VERSION"));
+
+/// Column options, unset ones fall back to the file defaults and then to `ColumnProperties::default()`
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnOptions {
+    page_size: Option<usize>,
+    dictionary_enabled: Option<bool>,
+    dictionary_page_size_limit: Option<usize>,
+    encoding: Option<Encoding>,
+    statistics_enabled: Option<bool>,
+    column_index_truncate_length: Option<Option<usize>>,
+    compression: Option<CompressionCodec>,
+    writer_version: Option<WriterVersion>,
+    int96_timestamps: Option<bool>,
+}
+
+impl ColumnOptions {
+    pub fn page_size(mut self, page_size: usize) -> ColumnOptions {
+        self.page_size = Some(page_size);
+        self
+    }
+
+    pub fn dictionary_enabled(mut self, enabled: bool) -> ColumnOptions {
+        self.dictionary_enabled = Some(enabled);
+        self
+    }
+
+    pub fn dictionary_page_size_limit(mut self, limit: usize) -> ColumnOptions {
+        self.dictionary_page_size_limit = Some(limit);
+        self
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> ColumnOptions {
+        self.encoding = Some(encoding);
+        self
+    }
+
+    pub fn statistics_enabled(mut self, enabled: bool) -> ColumnOptions {
+        self.statistics_enabled = Some(enabled);
+        self
+    }
+
+    pub fn column_index_truncate_length(mut self, length: Option<usize>) -> ColumnOptions {
+        self.column_index_truncate_length = Some(length);
+        self
+    }
+
+    pub fn compression(mut self, codec: CompressionCodec) -> ColumnOptions {
+        self.compression = Some(codec);
+        self
+    }
+
+    pub fn writer_version(mut self, version: WriterVersion) -> ColumnOptions {
+        self.writer_version = Some(version);
+        self
+    }
+
+    /// Write INT64 timestamps as legacy INT96, for compatibility with old Hive and Impala readers
+    pub fn int96_timestamps(mut self, enabled: bool) -> ColumnOptions {
+        self.int96_timestamps = Some(enabled);
+        self
+    }
+
+    fn apply(&self, props: &mut ColumnProperties) {
+        props.page_size = self.page_size.unwrap_or(props.page_size);
+        props.dictionary_enabled = self.dictionary_enabled.unwrap_or(props.dictionary_enabled);
+        props.dictionary_page_size_limit = self.dictionary_page_size_limit.unwrap_or(props.dictionary_page_size_limit);
+        props.encoding = self.encoding.unwrap_or(props.encoding);
+        props.statistics_enabled = self.statistics_enabled.unwrap_or(props.statistics_enabled);
+        props.column_index_truncate_length = self.column_index_truncate_length.unwrap_or(props.column_index_truncate_length);
+        props.compression = self.compression.unwrap_or(props.compression);
+        props.writer_version = self.writer_version.unwrap_or(props.writer_version);
+    }
+}
+
+/// Options of a file writer, e.g.
+///
+/// ```text
+/// WriterProperties::builder().
+///     max_row_group_rows(100_000).
+///     defaults(|c| c.compression(CompressionCodec::SNAPPY)).
+///     column("user_id", |c| c.dictionary_enabled(false).encoding(Encoding::DELTA_BINARY_PACKED)).
+///     column("country", |c| c.page_size(8 * 1024)).
+///     build()
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WriterProperties {
+    created_by: String,
+    key_value_metadata: Vec<KeyValue>,
+    max_row_group_rows: usize,
+    max_row_group_size: usize,
+    defaults: ColumnOptions,
+    /// Options by dot-separated column path
+    columns: Vec<(String, ColumnOptions)>,
+}
+
+impl Default for WriterProperties {
+    fn default() -> WriterProperties {
+        WriterProperties::builder().build()
+    }
+}
+
+impl WriterProperties {
+    pub fn builder() -> WriterPropertiesBuilder {
+        WriterPropertiesBuilder {
+            props: WriterProperties {
+                created_by: CREATED_BY.to_string(),
+                key_value_metadata: vec![],
+                max_row_group_rows: DEFAULT_MAX_ROW_GROUP_ROWS,
+                max_row_group_size: DEFAULT_MAX_ROW_GROUP_SIZE,
+                defaults: ColumnOptions::default(),
+                columns: vec![],
+            }
+        }
+    }
+
+    pub fn created_by(&self) -> &str {
+        &self.created_by
+    }
+
+    pub fn key_value_metadata(&self) -> &[KeyValue] {
+        &self.key_value_metadata
+    }
+
+    pub fn max_row_group_rows(&self) -> usize {
+        self.max_row_group_rows
+    }
+
+    /// Estimated encoded size in bytes at which a buffered row group is flushed
+    pub fn max_row_group_size(&self) -> usize {
+        self.max_row_group_size
+    }
+
+    /// Paths of columns with their own options
+    pub fn column_paths(&self) -> Vec<&str> {
+        self.columns.iter().map(|(path, _)| path.as_str()).collect()
+    }
+
+    fn options(&self, path: &str) -> Option<&ColumnOptions> {
+        self.columns.iter().find(|(p, _)| p == path).map(|(_, options)| options)
+    }
+
+    /// Properties of a column writer of the column with dot-separated `path`
+    pub fn column_properties(&self, path: &str) -> ColumnProperties {
+        let mut props = ColumnProperties::default();
+        self.defaults.apply(&mut props);
+        if let Some(options) = self.options(path) {
+            options.apply(&mut props);
+        }
+        props
+    }
+
+    /// Whether timestamps of the column with dot-separated `path` are written as INT96
+    pub fn int96_timestamps(&self, path: &str) -> bool {
+        self.options(path).and_then(|o| o.int96_timestamps).
+            or(self.defaults.int96_timestamps).
+            unwrap_or(false)
+    }
+
+    /// File format version: 2 if data pages of any column may be DATA_PAGE_V2
+    pub fn version(&self) -> i32 {
+        let v2 = |options: &ColumnOptions| options.writer_version == Some(WriterVersion::V2);
+        if v2(&self.defaults) || self.columns.iter().any(|(_, options)| v2(options)) { 2 } else { 1 }
+    }
+}
+
+pub struct WriterPropertiesBuilder {
+    props: WriterProperties,
+}
+
+impl WriterPropertiesBuilder {
+    pub fn created_by(mut self, created_by: &str) -> WriterPropertiesBuilder {
+        self.props.created_by = created_by.to_string();
+        self
+    }
+
+    /// Add key-value pair to file metadata, replacing an earlier value of `key`
+    pub fn key_value(mut self, key: &str, value: &str) -> WriterPropertiesBuilder {
+        self.props.key_value_metadata.retain(|kv| kv.key != key);
+        self.props.key_value_metadata.push(KeyValue::new(key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn max_row_group_rows(mut self, rows: usize) -> WriterPropertiesBuilder {
+        self.props.max_row_group_rows = rows;
+        self
+    }
+
+    pub fn max_row_group_size(mut self, size: usize) -> WriterPropertiesBuilder {
+        self.props.max_row_group_size = size;
+        self
+    }
+
+    /// Options of all columns
+    pub fn defaults<F: FnOnce(ColumnOptions) -> ColumnOptions>(mut self, options: F) -> WriterPropertiesBuilder {
+        self.props.defaults = options(self.props.defaults);
+        self
+    }
+
+    /// Options of the column with dot-separated `path`, on top of the defaults
+    pub fn column<F: FnOnce(ColumnOptions) -> ColumnOptions>(mut self, path: &str, options: F) -> WriterPropertiesBuilder {
+        match self.props.columns.iter().position(|(p, _)| p == path) {
+            Some(i) => {
+                let current = self.props.columns[i].1.clone();
+                self.props.columns[i].1 = options(current);
+            },
+            None => self.props.columns.push((path.to_string(), options(ColumnOptions::default()))),
+        }
+        self
+    }
+
+    pub fn build(self) -> WriterProperties {
+        self.props
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_overrides() {
+        let props = WriterProperties::builder().
+            defaults(|c| c.compression(CompressionCodec::SNAPPY).page_size(4096)).
+            column("id", |c| c.dictionary_enabled(false).encoding(Encoding::DELTA_BINARY_PACKED)).
+            column("a.b", |c| c.page_size(128)).
+            column("a.b", |c| c.statistics_enabled(false).int96_timestamps(true)).
+            key_value("origin", "test").
+            key_value("origin", "tests").
+            build();
+
+        let id = props.column_properties("id");
+        assert_eq!((false, Encoding::DELTA_BINARY_PACKED), (id.dictionary_enabled, id.encoding));
+        assert_eq!((4096, CompressionCodec::SNAPPY), (id.page_size, id.compression));
+
+        let b = props.column_properties("a.b");
+        assert_eq!((128, false, true), (b.page_size, b.statistics_enabled, b.dictionary_enabled));
+        assert!(props.int96_timestamps("a.b"));
+        assert!(!props.int96_timestamps("id"));
+
+        let other = props.column_properties("c");
+        assert_eq!(ColumnProperties { page_size: 4096, compression: CompressionCodec::SNAPPY, ..ColumnProperties::default() }, other);
+        assert_eq!(vec!["id", "a.b"], props.column_paths());
+        assert_eq!(&[KeyValue::new("origin".to_string(), "tests".to_string())], props.key_value_metadata());
+        assert_eq!(1, props.version());
+
+        let props = WriterProperties::builder().column("id", |c| c.writer_version(WriterVersion::V2)).build();
+        assert_eq!((WriterVersion::V2, WriterVersion::V1),
+            (props.column_properties("id").writer_version, props.column_properties("c").writer_version));
+        assert_eq!(2, props.version());
+    }
+}
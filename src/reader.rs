@@ -0,0 +1,330 @@
+use std::io::{Read, Seek, SeekFrom, Cursor, Result, Error, ErrorKind};
+use byteorder::{ByteOrder, LittleEndian};
+use thrift::protocol::TCompactInputProtocol;
+use parquet::*;
+use schema::ColumnDescriptor;
+use value::Value;
+use encodings::{self, BitPackingRleReader, RleIter, bit_width, decode_plain, decode_dictionary_indices};
+use compression::decompress;
+use MAGIC;
+
+/// Decoded column chunk. Levels have an entry per value slot, including nulls,
+/// and are zero when the column has no such levels. `values` holds non-null values only.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ColumnData {
+    pub def_levels: Vec<i16>,
+    pub rep_levels: Vec<i16>,
+    pub values: Vec<Value>,
+}
+
+fn invalid<E: ToString>(e: E) -> Error {
+    Error::new(ErrorKind::InvalidData, e.to_string())
+}
+
+/// Read `FileMetaData` from the footer of a file
+pub fn read_metadata<R: Read + Seek>(file: &mut R) -> Result<FileMetaData> {
+    let file_len = file.seek(SeekFrom::End(0))?;
+    if file_len < 12 {
+        return Err(invalid(format!("File of {} bytes is too short for parquet", file_len)))
+    }
+    file.seek(SeekFrom::End(-8))?;
+    let mut footer = [0_u8; 8];
+    file.read_exact(&mut footer)?;
+    if &footer[4..] != MAGIC.as_bytes() {
+        return Err(invalid("File does not end with parquet magic"))
+    }
+
+    let meta_len = LittleEndian::read_u32(&footer) as u64;
+    if meta_len + 12 > file_len {
+        return Err(invalid(format!("Metadata of {} bytes does not fit file of {} bytes", meta_len, file_len)))
+    }
+    file.seek(SeekFrom::End(-(meta_len as i64) - 8))?;
+    let mut protocol = TCompactInputProtocol::new(file.take(meta_len));
+    FileMetaData::read_from_in_protocol(&mut protocol).
+        map_err(|e| invalid(format!("Failed to deserialize FileMetaData: {}", e)))
+}
+
+/// Read and decode all pages of a column chunk
+pub fn read_column_chunk<R: Read + Seek>(file: &mut R, chunk: &ColumnChunk, column: &ColumnDescriptor) -> Result<ColumnData> {
+    let meta = chunk.meta_data.as_ref().
+        ok_or_else(|| invalid("ColumnChunk does not have metadata"))?;
+
+    // Some writers set dictionary_page_offset to 0 when there is no dictionary
+    let start = match meta.dictionary_page_offset {
+        Some(offset) if offset > 0 && offset < meta.data_page_offset => offset,
+        _ => meta.data_page_offset,
+    };
+    if start < 0 || meta.total_compressed_size < 0 {
+        return Err(invalid(format!("Column chunk of {} bytes at {} is out of file", meta.total_compressed_size, start)))
+    }
+    file.seek(SeekFrom::Start(start as u64))?;
+    // read up to the size rather than allocating it, a corrupt size may be larger than the file
+    let mut buffer = vec![];
+    file.take(meta.total_compressed_size as u64).read_to_end(&mut buffer)?;
+    if (buffer.len() as i64) < meta.total_compressed_size {
+        return Err(invalid(format!("Column chunk of {} bytes at {} is out of file", meta.total_compressed_size, start)))
+    }
+
+    let mut data = ColumnData::default();
+    let mut dictionary: Option<Vec<Value>> = None;
+    let mut cursor = Cursor::new(&buffer[..]);
+
+    while (cursor.position() as usize) < buffer.len() && (data.def_levels.len() as i64) < meta.num_values {
+        let header = {
+            let mut protocol = TCompactInputProtocol::new(&mut cursor);
+            PageHeader::read_from_in_protocol(&mut protocol).
+                map_err(|e| invalid(format!("Failed to deserialize PageHeader: {}", e)))?
+        };
+        if header.compressed_page_size < 0 || header.uncompressed_page_size < 0 {
+            return Err(invalid(format!("Negative sizes in page header {:?}", header)))
+        }
+        let page_start = cursor.position() as usize;
+        let page_end = match page_start.checked_add(header.compressed_page_size as usize) {
+            Some(page_end) if page_end <= buffer.len() => page_end,
+            _ => return Err(invalid(format!("Page of {} bytes at {} is out of column chunk", header.compressed_page_size, page_start))),
+        };
+        cursor.set_position(page_end as u64);
+        let page = &buffer[page_start .. page_end];
+
+        match header.type_ {
+            PageType::DICTIONARY_PAGE => {
+                let dict_header = header.dictionary_page_header.as_ref().
+                    ok_or_else(|| invalid("Dictionary page without header"))?;
+                if dict_header.num_values < 0 {
+                    return Err(invalid(format!("Dictionary page of {} values", dict_header.num_values)))
+                }
+                let page = decompress(meta.codec, page, header.uncompressed_page_size as usize)?;
+                let (values, _) = decode_plain(column.type_, column.type_length.unwrap_or(0), &page, dict_header.num_values as usize).
+                    map_err(invalid)?;
+                dictionary = Some(values);
+            },
+            PageType::DATA_PAGE => {
+                let data_header = header.data_page_header.as_ref().
+                    ok_or_else(|| invalid("Data page without header"))?;
+                let page = decompress(meta.codec, page, header.uncompressed_page_size as usize)?;
+                read_data_page_v1(data_header, &page, column, dictionary.as_ref(), &mut data)?;
+            },
+            PageType::DATA_PAGE_V2 => {
+                let data_header = header.data_page_header_v2.as_ref().
+                    ok_or_else(|| invalid("Data page v2 without header"))?;
+                read_data_page_v2(data_header, &header, page, meta.codec, column, dictionary.as_ref(), &mut data)?;
+            },
+            PageType::INDEX_PAGE => {},
+        }
+    }
+
+    Ok(data)
+}
+
+fn read_data_page_v1(header: &DataPageHeader, page: &[u8], column: &ColumnDescriptor,
+                     dictionary: Option<&Vec<Value>>, data: &mut ColumnData) -> Result<()> {
+    if header.num_values < 0 {
+        return Err(invalid(format!("Data page of {} values", header.num_values)))
+    }
+    let num_values = header.num_values as usize;
+    let mut pos = 0_usize;
+
+    let read_levels = |max_level: i16, encoding: Encoding, pos: &mut usize| -> Result<Vec<i16>> {
+        if max_level == 0 {
+            return Ok(vec![0; num_values])
+        }
+        if encoding != Encoding::RLE {
+            return Err(invalid(format!("Level encoding is not implemented yet: {:?}", encoding)))
+        }
+        let reader = BitPackingRleReader::new(max_level as u32, &page[*pos..]).map_err(invalid)?;
+        *pos += reader.encoded_len();
+        collect_levels(reader.into_iter(), num_values)
+    };
+
+    let rep_levels = read_levels(column.max_rep_level, header.repetition_level_encoding, &mut pos)?;
+    let def_levels = read_levels(column.max_def_level, header.definition_level_encoding, &mut pos)?;
+
+    let non_null = def_levels.iter().filter(|&&d| d == column.max_def_level).count();
+    let values = decode_values(header.encoding, &page[pos..], non_null, column, dictionary)?;
+
+    data.rep_levels.extend(rep_levels);
+    data.def_levels.extend(def_levels);
+    data.values.extend(values);
+    Ok(())
+}
+
+fn read_data_page_v2(header: &DataPageHeaderV2, page_header: &PageHeader, page: &[u8], codec: CompressionCodec,
+                     column: &ColumnDescriptor, dictionary: Option<&Vec<Value>>, data: &mut ColumnData) -> Result<()> {
+    if header.num_values < 0 || header.repetition_levels_byte_length < 0 || header.definition_levels_byte_length < 0 ||
+        page_header.uncompressed_page_size < 0 {
+        return Err(invalid(format!("Negative sizes in page header {:?}", page_header)))
+    }
+    let num_values = header.num_values as usize;
+    let rep_len = header.repetition_levels_byte_length as usize;
+    let def_len = header.definition_levels_byte_length as usize;
+    if rep_len + def_len > page.len() {
+        return Err(invalid(format!("Levels of {} bytes do not fit page of {} bytes", rep_len + def_len, page.len())))
+    }
+
+    // Levels are never compressed in v2 pages and have no length prefix
+    let read_levels = |max_level: i16, levels: &[u8]| -> Result<Vec<i16>> {
+        if max_level == 0 {
+            return Ok(vec![0; num_values])
+        }
+        collect_levels(RleIter::new(levels, bit_width(max_level as u32)), num_values)
+    };
+    let rep_levels = read_levels(column.max_rep_level, &page[.. rep_len])?;
+    let def_levels = read_levels(column.max_def_level, &page[rep_len .. rep_len + def_len])?;
+
+    let values_data = &page[rep_len + def_len ..];
+    let values_data = if header.is_compressed.unwrap_or(true) {
+        let uncompressed_size = (page_header.uncompressed_page_size as usize).checked_sub(rep_len + def_len).
+            ok_or_else(|| invalid(format!("Levels of {} bytes do not fit uncompressed page of {} bytes",
+                rep_len + def_len, page_header.uncompressed_page_size)))?;
+        decompress(codec, values_data, uncompressed_size)?
+    } else {
+        values_data.to_vec()
+    };
+
+    let non_null = def_levels.iter().filter(|&&d| d == column.max_def_level).count();
+    let values = decode_values(header.encoding, &values_data, non_null, column, dictionary)?;
+
+    data.rep_levels.extend(rep_levels);
+    data.def_levels.extend(def_levels);
+    data.values.extend(values);
+    Ok(())
+}
+
+fn collect_levels<I: Iterator<Item=i32>>(levels: I, count: usize) -> Result<Vec<i16>> {
+    let levels: Vec<i16> = levels.take(count).map(|l| l as i16).collect();
+    if levels.len() < count {
+        return Err(invalid(format!("Expected {} levels but got {}", count, levels.len())))
+    }
+    Ok(levels)
+}
+
+fn decode_values(encoding: Encoding, data: &[u8], count: usize, column: &ColumnDescriptor,
+                 dictionary: Option<&Vec<Value>>) -> Result<Vec<Value>> {
+    match encoding {
+        Encoding::PLAIN_DICTIONARY | Encoding::RLE_DICTIONARY => {
+            let dictionary = dictionary.ok_or_else(|| invalid("Dictionary encoded page without dictionary page"))?;
+            decode_dictionary_indices(data, count).map_err(invalid)?.into_iter().
+                map(|i| dictionary.get(i).cloned().
+                    ok_or_else(|| invalid(format!("Dictionary index {} out of {} entries", i, dictionary.len())))).
+                collect()
+        },
+        _ => encodings::decode_values(encoding, column.type_, column.type_length.unwrap_or(0), data, count).map_err(invalid),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use thrift::protocol::{TCompactOutputProtocol, TOutputProtocol};
+
+    /// Uncompressed v1 data page of an optional INT64 column with PLAIN values
+    pub fn optional_int64_page(values: &[Option<i64>]) -> Vec<u8> {
+        // definition levels: bit-packed, bit width 1
+        let groups = values.len().div_ceil(8);
+        let mut levels = vec![(groups << 1 | 1) as u8];
+        let mut packed = vec![0_u8; groups];
+        for (i, v) in values.iter().enumerate() {
+            if v.is_some() { packed[i / 8] |= 1 << (i % 8) }
+        }
+        levels.extend(packed);
+
+        let mut body = vec![levels.len() as u8, 0, 0, 0];
+        body.extend(levels);
+        for v in values.iter().filter_map(|v| *v) {
+            body.extend(Value::Int64(v).to_plain());
+        }
+
+        let data_header = DataPageHeader::new(values.len() as i32, Encoding::PLAIN, Encoding::RLE, Encoding::RLE, None);
+        let header = PageHeader::new(PageType::DATA_PAGE, body.len() as i32, body.len() as i32, None, data_header, None, None, None);
+        let mut page = Vec::new();
+        {
+            let mut protocol = TCompactOutputProtocol::new(&mut page);
+            header.write_to_out_protocol(&mut protocol).unwrap();
+            protocol.flush().unwrap();
+        }
+        page.extend(body);
+        page
+    }
+
+    pub fn int64_column(max_def_level: i16) -> ColumnDescriptor {
+        ColumnDescriptor {
+            path: vec!["v".to_string()],
+            type_: Type::INT64,
+            type_length: None,
+            max_def_level,
+            max_rep_level: 0,
+            schema_idx: 1,
+        }
+    }
+
+    pub fn chunk(offset: i64, len: usize, num_values: i64) -> ColumnChunk {
+        let meta = ColumnMetaData::new(Type::INT64, vec![Encoding::PLAIN, Encoding::RLE], vec!["v".to_string()],
+            CompressionCodec::UNCOMPRESSED, num_values, len as i64, len as i64, None, offset, None, None, None, None);
+        ColumnChunk::new(None, offset, meta, None, None, None, None)
+    }
+
+    #[test]
+    fn read_optional_column() {
+        let mut file = b"PAR1".to_vec();
+        let page = optional_int64_page(&[Some(1), None, Some(3)]);
+        let len = page.len();
+        file.extend(page);
+
+        let data = read_column_chunk(&mut Cursor::new(file), &chunk(4, len, 3), &int64_column(1)).unwrap();
+        assert_eq!(vec![1, 0, 1], data.def_levels);
+        assert_eq!(vec![0, 0, 0], data.rep_levels);
+        assert_eq!(vec![Value::Int64(1), Value::Int64(3)], data.values);
+    }
+
+    #[test]
+    fn malformed_v2_page_sizes() {
+        let read = |rep_len: i32, def_len: i32, uncompressed_size: i32| {
+            let header = DataPageHeaderV2::new(1, 0, 1, Encoding::PLAIN, def_len, rep_len, true, None);
+            let page_header = PageHeader::new(PageType::DATA_PAGE_V2, uncompressed_size, 12, None, None, None, None, header.clone());
+            // one defined value as an RLE run
+            let mut page = vec![2, 1];
+            page.resize(12, 0);
+            read_data_page_v2(&header, &page_header, &page, CompressionCodec::SNAPPY, &int64_column(1), None,
+                &mut ColumnData::default()).unwrap_err().kind()
+        };
+        // levels larger than the uncompressed page, negative sizes
+        assert_eq!(ErrorKind::InvalidData, read(0, 2, 1));
+        assert_eq!(ErrorKind::InvalidData, read(0, 2, -1));
+        assert_eq!(ErrorKind::InvalidData, read(-1, 2, 12));
+    }
+
+    #[test]
+    fn malformed_chunk_sizes() {
+        let read = |chunk: &ColumnChunk, file: &[u8]| {
+            read_column_chunk(&mut Cursor::new(file.to_vec()), chunk, &int64_column(1)).unwrap_err().kind()
+        };
+        let mut file = b"PAR1".to_vec();
+        file.extend(optional_int64_page(&[Some(1)]));
+        let len = file.len() - 4;
+
+        let mut negative = chunk(4, len, 1);
+        negative.meta_data.as_mut().unwrap().total_compressed_size = -1;
+        assert_eq!(ErrorKind::InvalidData, read(&negative, &file));
+        let mut beyond_file = chunk(4, len, 1);
+        beyond_file.meta_data.as_mut().unwrap().total_compressed_size = i64::MAX;
+        assert_eq!(ErrorKind::InvalidData, read(&beyond_file, &file));
+
+        // page headers with negative sizes and value counts
+        let page = |compressed_size: i32, num_values: i32| {
+            let data_header = DataPageHeader::new(num_values, Encoding::PLAIN, Encoding::RLE, Encoding::RLE, None);
+            let header = PageHeader::new(PageType::DATA_PAGE, 4, compressed_size, None, data_header, None, None, None);
+            let mut file = b"PAR1".to_vec();
+            {
+                let mut protocol = TCompactOutputProtocol::new(&mut file);
+                header.write_to_out_protocol(&mut protocol).unwrap();
+                protocol.flush().unwrap();
+            }
+            file.extend(vec![0; 4]);
+            file
+        };
+        let file = page(-8, 1);
+        assert_eq!(ErrorKind::InvalidData, read(&chunk(4, file.len() - 4, 1), &file));
+        let file = page(4, -1);
+        assert_eq!(ErrorKind::InvalidData, read(&chunk(4, file.len() - 4, 1), &file));
+    }
+}
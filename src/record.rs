@@ -0,0 +1,976 @@
+use std::io::{Read, Seek, Result, Error, ErrorKind};
+use std::mem;
+use parquet::*;
+use value::{Value, comparable, is_float16, f32_to_float16};
+use logical::{Logical, Uuid, resolve};
+use decimal::Decimal;
+use temporal::{Temporal, temporal_type};
+use reader::{ColumnData, read_column_chunk};
+use schema::{leaf_columns, find_column};
+
+/// Record, i.e. group of named fields
+#[derive(Clone, Debug, PartialEq)]
+pub struct Row {
+    pub fields: Vec<(String, Field)>,
+}
+
+impl Row {
+    pub fn get(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|f| f.0 == name).map(|f| &f.1)
+    }
+}
+
+/// Field value with logical type applied
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field {
+    Null,
+    Boolean(bool),
+    Int32(i32),
+    Int64(i64),
+    Float(f32),
+    Double(f64),
+    Bytes(Vec<u8>),
+    Str(String),
+    /// ENUM column value
+    Enum(String),
+    /// JSON document as text
+    Json(String),
+    /// BSON document
+    Bson(Vec<u8>),
+    Uuid(Uuid),
+    Decimal(Decimal),
+    Temporal(Temporal),
+    Group(Row),
+    List(Vec<Field>),
+    Map(Vec<(Field, Field)>),
+}
+
+impl Field {
+    /// Parse JSON column value. Error means the document is not valid JSON.
+    /// None if the field is not JSON.
+    #[cfg(feature = "serde_json")]
+    pub fn parse_json(&self) -> Option<::serde_json::Result<::serde_json::Value>> {
+        match *self {
+            Field::Json(ref json) => Some(::serde_json::from_str(json)),
+            _ => None,
+        }
+    }
+}
+
+/// Schema tree node
+#[derive(Debug)]
+struct Node {
+    element: SchemaElement,
+    kind: Kind,
+    children: Vec<Node>,
+    def_level: i16,
+    rep_level: i16,
+    /// Range of leaf columns under this node
+    first_leaf: usize,
+    leaf_count: usize,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Primitive(Option<Logical>),
+    Group,
+    /// LIST annotated group, its only child is the repeated field
+    List(ListShape),
+    /// MAP annotated group, its only child is the repeated key-value group
+    Map,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ListShape {
+    /// Legacy 2-level list: repeated field is the element
+    TwoLevel,
+    /// Standard 3-level list: repeated group wraps the element
+    ThreeLevel,
+}
+
+impl Node {
+    fn repetition(&self) -> FieldRepetitionType {
+        self.element.repetition_type.unwrap_or(FieldRepetitionType::REQUIRED)
+    }
+}
+
+/// Build schema tree from flattened schema
+fn build_tree(schema: &[SchemaElement]) -> std::result::Result<Node, String> {
+    let mut pos = 0;
+    let mut leaf = 0;
+    let root = build_node(schema, &mut pos, 0, 0, &mut leaf, true)?;
+    if pos != schema.len() {
+        return Err(format!("Schema has {} elements but only {} are reachable from the root", schema.len(), pos))
+    }
+    Ok(root)
+}
+
+fn build_node(schema: &[SchemaElement], pos: &mut usize, def_level: i16, rep_level: i16, leaf: &mut usize, root: bool)
+    -> std::result::Result<Node, String>
+{
+    let element = schema.get(*pos).ok_or("Unexpected end of schema")?.clone();
+    *pos += 1;
+
+    let (def_level, rep_level) = match element.repetition_type {
+        _ if root => (0, 0),
+        Some(FieldRepetitionType::OPTIONAL) => (def_level + 1, rep_level),
+        Some(FieldRepetitionType::REPEATED) => (def_level + 1, rep_level + 1),
+        _ => (def_level, rep_level),
+    };
+
+    let first_leaf = *leaf;
+    let logical = resolve(&element)?;
+    let num_children = element.num_children.unwrap_or(0);
+
+    if element.type_.is_some() && num_children == 0 {
+        *leaf += 1;
+        return Ok(Node { element, kind: Kind::Primitive(logical), children: vec![],
+            def_level, rep_level, first_leaf, leaf_count: 1 })
+    }
+
+    let mut children = Vec::with_capacity(num_children as usize);
+    for _ in 0..num_children {
+        children.push(build_node(schema, pos, def_level, rep_level, leaf, false)?);
+    }
+
+    let kind = match logical {
+        Some(Logical::List) => list_shape(&element, &children).map_or(Kind::Group, Kind::List),
+        // MAP_KEY_VALUE was meant for the key-value group, but some writers put it on the map itself
+        Some(Logical::Map) | Some(Logical::MapKeyValue) if is_map(&children) => Kind::Map,
+        _ => Kind::Group,
+    };
+
+    Ok(Node { element, kind, children, def_level, rep_level, first_leaf, leaf_count: *leaf - first_leaf })
+}
+
+/// Element shape of a LIST annotated group, following format's backward-compatibility rules.
+/// None if the group does not look like a list.
+fn list_shape(list: &SchemaElement, children: &[Node]) -> Option<ListShape> {
+    if children.len() != 1 || children[0].repetition() != FieldRepetitionType::REPEATED {
+        return None
+    }
+
+    let repeated = &children[0];
+    let two_level =
+        // repeated primitive: list of required elements
+        repeated.children.is_empty() ||
+        // repeated group of several fields is the element struct
+        repeated.children.len() > 1 ||
+        // Avro and Thrift name one-field element structs "array" and "<list>_tuple"
+        repeated.element.name == "array" ||
+        repeated.element.name == format!("{}_tuple", list.name);
+
+    Some(if two_level { ListShape::TwoLevel } else { ListShape::ThreeLevel })
+}
+
+/// MAP group must wrap a repeated key-value group with key and optional value
+fn is_map(children: &[Node]) -> bool {
+    children.len() == 1 &&
+        children[0].repetition() == FieldRepetitionType::REPEATED &&
+        !children[0].children.is_empty() &&
+        children[0].children.len() <= 2
+}
+
+/// How to read each element of a repeated field
+#[derive(Clone, Copy)]
+enum Element {
+    /// Repeated field itself is the element
+    Itself,
+    /// Only child of the repeated group is the element
+    OnlyChild,
+    /// Repeated group holds key and value
+    KeyValue,
+}
+
+struct LeafCursor {
+    data: ColumnData,
+    level: usize,
+    value: usize,
+}
+
+/// Assembles records from column levels and values (Dremel record assembly)
+struct Assembler {
+    leaves: Vec<LeafCursor>,
+}
+
+impl Assembler {
+    fn has_more(&self) -> bool {
+        self.leaves.first().is_some_and(|l| l.level < l.data.def_levels.len())
+    }
+
+    fn peek(&self, leaf: usize) -> Option<(i16, i16)> {
+        let l = &self.leaves[leaf];
+        if l.level < l.data.def_levels.len() {
+            Some((l.data.rep_levels[l.level], l.data.def_levels[l.level]))
+        } else {
+            None
+        }
+    }
+
+    fn peek_def(&self, node: &Node) -> std::result::Result<i16, String> {
+        self.peek(node.first_leaf).map(|(_, def)| def).
+            ok_or_else(|| format!("Unexpected end of column data for '{}'", node.element.name))
+    }
+
+    /// Consume next entry of a leaf, value is present only for non-null entries
+    fn consume(&mut self, leaf: usize, max_def: i16) -> std::result::Result<Option<Value>, String> {
+        let l = &mut self.leaves[leaf];
+        if l.level >= l.data.def_levels.len() {
+            return Err(format!("Unexpected end of levels in leaf column {}", leaf))
+        }
+        let def = l.data.def_levels[l.level];
+        l.level += 1;
+        if def < max_def {
+            return Ok(None)
+        }
+        let value = l.data.values.get(l.value).cloned().
+            ok_or_else(|| format!("Unexpected end of values in leaf column {}", leaf))?;
+        l.value += 1;
+        Ok(Some(value))
+    }
+
+    /// Node is null or empty here: every leaf under it has exactly one entry
+    fn skip(&mut self, node: &Node) -> std::result::Result<(), String> {
+        for leaf in node.first_leaf .. node.first_leaf + node.leaf_count {
+            self.consume(leaf, i16::MAX)?;
+        }
+        Ok(())
+    }
+
+    fn read_row(&mut self, root: &Node) -> std::result::Result<Row, String> {
+        let fields = root.children.iter().
+            map(|child| Ok((child.element.name.clone(), self.read_field(child)?))).
+            collect::<std::result::Result<Vec<_>, String>>()?;
+        Ok(Row { fields })
+    }
+
+    /// Read one occurrence of the field, assuming its parent is present
+    fn read_field(&mut self, node: &Node) -> std::result::Result<Field, String> {
+        match node.repetition() {
+            // repeated field outside of LIST annotation is a list of required elements
+            FieldRepetitionType::REPEATED => Ok(Field::List(self.read_repeated(node, Element::Itself)?)),
+            FieldRepetitionType::OPTIONAL if self.peek_def(node)? < node.def_level => {
+                self.skip(node)?;
+                Ok(Field::Null)
+            },
+            _ => self.read_value(node),
+        }
+    }
+
+    /// Read all elements of a repeated field
+    fn read_repeated(&mut self, node: &Node, element: Element) -> std::result::Result<Vec<Field>, String> {
+        if self.peek_def(node)? < node.def_level {
+            self.skip(node)?;
+            return Ok(vec![])
+        }
+
+        let mut items = vec![];
+        loop {
+            items.push(match element {
+                Element::Itself => self.read_value(node)?,
+                Element::OnlyChild => self.read_field(&node.children[0])?,
+                Element::KeyValue => {
+                    let key = self.read_field(&node.children[0])?;
+                    let value = match node.children.get(1) {
+                        Some(value) => self.read_field(value)?,
+                        None => Field::Null,
+                    };
+                    Field::List(vec![key, value])
+                }
+            });
+
+            match self.peek(node.first_leaf) {
+                Some((rep, _)) if rep == node.rep_level => continue,
+                _ => break,
+            }
+        }
+        Ok(items)
+    }
+
+    /// Read value of a present node
+    fn read_value(&mut self, node: &Node) -> std::result::Result<Field, String> {
+        match node.kind {
+            Kind::Primitive(logical) => {
+                let value = self.consume(node.first_leaf, node.def_level)?.
+                    ok_or_else(|| format!("Missing value of '{}'", node.element.name))?;
+                convert(value, &node.element, logical)
+            },
+            Kind::Group => Ok(Field::Group(self.read_row(node)?)),
+            Kind::List(shape) => {
+                let element = if shape == ListShape::TwoLevel { Element::Itself } else { Element::OnlyChild };
+                Ok(Field::List(self.read_repeated(&node.children[0], element)?))
+            },
+            Kind::Map => {
+                let entries = self.read_repeated(&node.children[0], Element::KeyValue)?.into_iter().
+                    map(|kv| match kv {
+                        Field::List(mut kv) => {
+                            let value = kv.pop().unwrap_or(Field::Null);
+                            let key = kv.pop().unwrap_or(Field::Null);
+                            (key, value)
+                        },
+                        other => (other, Field::Null),
+                    }).collect();
+                Ok(Field::Map(entries))
+            },
+        }
+    }
+}
+
+/// Apply logical type to a physical value
+fn convert(value: Value, element: &SchemaElement, logical: Option<Logical>) -> std::result::Result<Field, String> {
+    match (logical, value) {
+        // Text which is not valid UTF-8 is kept as bytes
+        (Some(Logical::String), Value::Bytes(bytes)) => Ok(String::from_utf8(bytes).
+            map_or_else(|e| Field::Bytes(e.into_bytes()), Field::Str)),
+        (Some(Logical::Enum), Value::Bytes(bytes)) => Ok(String::from_utf8(bytes).
+            map_or_else(|e| Field::Bytes(e.into_bytes()), Field::Enum)),
+        (Some(Logical::Json), Value::Bytes(bytes)) => Ok(String::from_utf8(bytes).
+            map_or_else(|e| Field::Bytes(e.into_bytes()), Field::Json)),
+        (Some(Logical::Bson), Value::Bytes(bytes)) => Ok(Field::Bson(bytes)),
+        (Some(Logical::Uuid), Value::Bytes(bytes)) => Ok(Field::Uuid(Uuid::from_bytes(&bytes)?)),
+        (Some(Logical::Float16), value) => match comparable(element, value)? {
+            Value::Float(v) => Ok(Field::Float(v)),
+            value => Err(format!("Value can not be a FLOAT16: {:?}", value)),
+        },
+        (Some(Logical::Decimal { precision, scale }), value) =>
+            Ok(Field::Decimal(Decimal::from_value(&value, precision, scale)?)),
+        (_, value) => {
+            if let Some(temporal) = temporal_type(element)? {
+                return Ok(Field::Temporal(temporal.decode(&value)?))
+            }
+            Ok(match value {
+                Value::Boolean(v) => Field::Boolean(v),
+                Value::Int32(v) => Field::Int32(v),
+                Value::Int64(v) => Field::Int64(v),
+                Value::Int96(v) => Field::Bytes(Value::Int96(v).to_plain()),
+                Value::Float(v) => Field::Float(v),
+                Value::Double(v) => Field::Double(v),
+                Value::Bytes(v) => Field::Bytes(v),
+            })
+        }
+    }
+}
+
+fn assemble(root: &Node, columns: Vec<ColumnData>) -> std::result::Result<Vec<Row>, String> {
+    let mut assembler = Assembler {
+        leaves: columns.into_iter().map(|data| LeafCursor { data, level: 0, value: 0 }).collect(),
+    };
+
+    let mut rows = vec![];
+    while assembler.has_more() {
+        rows.push(assembler.read_row(root)?);
+    }
+    Ok(rows)
+}
+
+/// Read all records of a row group
+pub fn read_row_group<R: Read + Seek>(file: &mut R, meta: &FileMetaData, row_group: usize) -> Result<Vec<Row>> {
+    let invalid = |e: String| Error::new(ErrorKind::InvalidData, e);
+    let root = build_tree(&meta.schema).map_err(invalid)?;
+    let columns = leaf_columns(&meta.schema).map_err(invalid)?;
+    let row_group = meta.row_groups.get(row_group).
+        ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Row group {} does not exist", row_group)))?;
+    if row_group.columns.len() != columns.len() {
+        return Err(invalid(format!("Row group has {} columns but schema has {}", row_group.columns.len(), columns.len())))
+    }
+
+    let data = row_group.columns.iter().zip(columns.iter()).
+        map(|(chunk, column)| read_column_chunk(file, chunk, column)).
+        collect::<Result<Vec<_>>>()?;
+    assemble(&root, data).map_err(invalid)
+}
+
+/// Iterator over records of all row groups, reading one row group at a time
+pub struct RowIter<'a, R: 'a + Read + Seek> {
+    file: &'a mut R,
+    meta: &'a FileMetaData,
+    row_group: usize,
+    rows: ::std::vec::IntoIter<Row>,
+}
+
+impl<'a, R: 'a + Read + Seek> RowIter<'a, R> {
+    pub fn new(file: &'a mut R, meta: &'a FileMetaData) -> RowIter<'a, R> {
+        RowIter { file, meta, row_group: 0, rows: vec![].into_iter() }
+    }
+}
+
+impl<'a, R: 'a + Read + Seek> Iterator for RowIter<'a, R> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(Ok(row))
+            }
+            if self.row_group >= self.meta.row_groups.len() {
+                return None
+            }
+            match read_row_group(self.file, self.meta, self.row_group) {
+                Ok(rows) => self.rows = rows.into_iter(),
+                Err(e) => {
+                    // do not retry failed row group
+                    self.row_group = self.meta.row_groups.len();
+                    return Some(Err(e))
+                }
+            }
+            self.row_group += 1;
+        }
+    }
+}
+
+/// Leaf column of a row group in columnar form, with the lists it is nested in: one level of lists
+/// per repeated field above the leaf, outermost first. LIST and MAP annotated groups follow the same
+/// backward-compatibility rules as rows. MAP keys and values are leaves that share offsets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct NestedColumn {
+    /// Offsets of lists of every level. Level 0 has a list per row, list `i` of a level holds slots
+    /// `offsets[i]..offsets[i + 1]` of the next level, or of `values` at the innermost level.
+    pub offsets: Vec<Vec<usize>>,
+    /// Whether each list of every level is present, a null list has no slots
+    pub validity: Vec<Vec<bool>>,
+    /// Innermost slots with logical type applied, None if null. A slot per row if the leaf is not repeated.
+    pub values: Vec<Option<Field>>,
+}
+
+/// Group levels and values of leaf column `leaf` into lists of its repeated fields
+fn nest(root: &Node, leaf: usize, data: ColumnData) -> std::result::Result<NestedColumn, String> {
+    // definition levels of repeated fields above the leaf, the leaf itself included
+    let mut repeated = vec![];
+    let mut node = root;
+    while let Some(child) = node.children.iter().find(|c| c.first_leaf <= leaf && leaf < c.first_leaf + c.leaf_count) {
+        if child.repetition() == FieldRepetitionType::REPEATED {
+            repeated.push(child.def_level);
+        }
+        node = child;
+    }
+    let logical = match node.kind {
+        Kind::Primitive(logical) if node.first_leaf == leaf => logical,
+        _ => return Err(format!("Schema has no leaf column {}", leaf)),
+    };
+
+    let depth = repeated.len();
+    let mut nested = NestedColumn { offsets: vec![vec![]; depth], validity: vec![vec![]; depth], values: vec![] };
+    let mut values = data.values.into_iter();
+    for (&rep, &def) in data.rep_levels.iter().zip(data.def_levels.iter()) {
+        if rep as usize > depth {
+            return Err(format!("Repetition level {} of '{}' is over {}", rep, node.element.name, depth))
+        }
+        for level in rep as usize .. depth {
+            // a list starts at every level below the repetition level where its parent slot is present
+            if level > 0 && def < repeated[level - 1] {
+                break
+            }
+            let slots = if level + 1 < depth { nested.offsets[level + 1].len() } else { nested.values.len() };
+            nested.offsets[level].push(slots);
+            // the repeated field's parent is defined one level below it, the list is empty then
+            nested.validity[level].push(def >= repeated[level] - 1);
+        }
+        if depth == 0 || def >= repeated[depth - 1] {
+            let value = if def == node.def_level {
+                let value = values.next().ok_or_else(|| format!("Unexpected end of values of '{}'", node.element.name))?;
+                Some(convert(value, &node.element, logical)?)
+            } else {
+                None
+            };
+            nested.values.push(value);
+        }
+    }
+    for level in 0..depth {
+        let slots = if level + 1 < depth { nested.offsets[level + 1].len() } else { nested.values.len() };
+        nested.offsets[level].push(slots);
+    }
+    Ok(nested)
+}
+
+/// Read the column at dot-separated `column_path` of a row group with its lists, see `NestedColumn`
+pub fn read_nested_column<R: Read + Seek>(file: &mut R, meta: &FileMetaData, row_group: usize, column_path: &str) -> Result<NestedColumn> {
+    let invalid = |e: String| Error::new(ErrorKind::InvalidData, e);
+    let root = build_tree(&meta.schema).map_err(invalid)?;
+    let columns = leaf_columns(&meta.schema).map_err(invalid)?;
+    let leaf = find_column(&columns, column_path).
+        ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Column not found: {}", column_path)))?;
+    let chunk = meta.row_groups.get(row_group).
+        ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Row group {} does not exist", row_group)))?.
+        columns.get(leaf).
+        ok_or_else(|| invalid(format!("Row group {} does not have column {}", row_group, leaf)))?;
+
+    let data = read_column_chunk(file, chunk, &columns[leaf])?;
+    nest(&root, leaf, data).map_err(invalid)
+}
+
+/// Splits records into levels and values of every leaf column (Dremel record shredding),
+/// the inverse of record assembly. Leaves are in schema order, as `leaf_columns` returns them.
+pub struct Shredder {
+    root: Node,
+    stripes: Stripes,
+    num_rows: usize,
+}
+
+/// Levels and values of leaf columns being shredded
+struct Stripes {
+    leaves: Vec<ColumnData>,
+    values_size: usize,
+}
+
+impl Shredder {
+    pub fn new(schema: &[SchemaElement]) -> std::result::Result<Shredder, String> {
+        let root = build_tree(schema)?;
+        let leaves = vec![ColumnData::default(); root.leaf_count];
+        Ok(Shredder { root, stripes: Stripes { leaves, values_size: 0 }, num_rows: 0 })
+    }
+
+    /// Number of records shredded since the last `take`
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    /// PLAIN encoded size of values shredded since the last `take`
+    pub fn values_size(&self) -> usize {
+        self.stripes.values_size
+    }
+
+    /// Shred a record. A record that does not match the schema leaves no entries behind.
+    pub fn write_row(&mut self, row: &Row) -> std::result::Result<(), String> {
+        let lens: Vec<_> = self.stripes.leaves.iter().map(|l| (l.def_levels.len(), l.values.len())).collect();
+        let values_size = self.stripes.values_size;
+        let res = self.stripes.write_group(&self.root, row, 0);
+        if res.is_err() {
+            for (leaf, &(levels, values)) in self.stripes.leaves.iter_mut().zip(lens.iter()) {
+                leaf.def_levels.truncate(levels);
+                leaf.rep_levels.truncate(levels);
+                leaf.values.truncate(values);
+            }
+            self.stripes.values_size = values_size;
+            return res
+        }
+        self.num_rows += 1;
+        Ok(())
+    }
+
+    /// Levels and values of every leaf column, resetting the shredder
+    pub fn take(&mut self) -> Vec<ColumnData> {
+        self.num_rows = 0;
+        self.stripes.values_size = 0;
+        let empty = vec![ColumnData::default(); self.stripes.leaves.len()];
+        mem::replace(&mut self.stripes.leaves, empty)
+    }
+}
+
+impl Stripes {
+    fn write_group(&mut self, node: &Node, row: &Row, rep: i16) -> std::result::Result<(), String> {
+        if let Some((name, _)) = row.fields.iter().find(|(name, _)| node.children.iter().all(|c| c.element.name != *name)) {
+            return Err(format!("Field '{}' is not in group '{}'", name, node.element.name))
+        }
+        for child in node.children.iter() {
+            self.write_field(child, row.get(&child.element.name), rep)?;
+        }
+        Ok(())
+    }
+
+    /// Write one occurrence of the field, assuming its parent is present. Missing field is null.
+    fn write_field(&mut self, node: &Node, field: Option<&Field>, rep: i16) -> std::result::Result<(), String> {
+        let field = field.unwrap_or(&Field::Null);
+        match node.repetition() {
+            // repeated field outside of LIST annotation is a list of required elements
+            FieldRepetitionType::REPEATED => match *field {
+                Field::List(ref items) => self.write_repeated(node, items, rep, |s, item, rep| s.write_value(node, item, rep)),
+                Field::Null => self.write_null(node, rep, node.def_level - 1),
+                _ => Err(format!("Expected list of '{}' but got {:?}", node.element.name, field)),
+            },
+            FieldRepetitionType::OPTIONAL if *field == Field::Null => self.write_null(node, rep, node.def_level - 1),
+            _ if *field == Field::Null => Err(format!("Required field '{}' is null", node.element.name)),
+            _ => self.write_value(node, field, rep),
+        }
+    }
+
+    /// Write elements of a repeated field, the first one at `rep` and the others at the field's level
+    fn write_repeated<T, F>(&mut self, node: &Node, items: &[T], rep: i16, write: F) -> std::result::Result<(), String>
+        where F: Fn(&mut Stripes, &T, i16) -> std::result::Result<(), String>
+    {
+        if items.is_empty() {
+            return self.write_null(node, rep, node.def_level - 1)
+        }
+        for (i, item) in items.iter().enumerate() {
+            write(self, item, if i == 0 { rep } else { node.rep_level })?;
+        }
+        Ok(())
+    }
+
+    /// Node is null or empty here: every leaf under it gets one entry at `def`
+    fn write_null(&mut self, node: &Node, rep: i16, def: i16) -> std::result::Result<(), String> {
+        for leaf in self.leaves[node.first_leaf .. node.first_leaf + node.leaf_count].iter_mut() {
+            leaf.def_levels.push(def);
+            leaf.rep_levels.push(rep);
+        }
+        Ok(())
+    }
+
+    /// Write value of a present node
+    fn write_value(&mut self, node: &Node, field: &Field, rep: i16) -> std::result::Result<(), String> {
+        match (&node.kind, field) {
+            (&Kind::Primitive(logical), _) => {
+                let value = unconvert(field, &node.element, logical)?;
+                self.values_size += value.plain_size();
+                let leaf = &mut self.leaves[node.first_leaf];
+                leaf.def_levels.push(node.def_level);
+                leaf.rep_levels.push(rep);
+                leaf.values.push(value);
+                Ok(())
+            },
+            (&Kind::Group, Field::Group(row)) => self.write_group(node, row, rep),
+            (&Kind::List(shape), Field::List(items)) => {
+                let repeated = &node.children[0];
+                self.write_repeated(repeated, items, rep, |s, item, rep| match shape {
+                    ListShape::TwoLevel => s.write_value(repeated, item, rep),
+                    ListShape::ThreeLevel => s.write_field(&repeated.children[0], Some(item), rep),
+                })
+            },
+            (&Kind::Map, Field::Map(entries)) => {
+                let key_value = &node.children[0];
+                self.write_repeated(key_value, entries, rep, |s, (key, value), rep| {
+                    s.write_field(&key_value.children[0], Some(key), rep)?;
+                    match key_value.children.get(1) {
+                        Some(child) => s.write_field(child, Some(value), rep),
+                        None if *value == Field::Null => Ok(()),
+                        None => Err(format!("Map '{}' has no values but got {:?}", node.element.name, value)),
+                    }
+                })
+            },
+            _ => Err(format!("Field {:?} does not match '{}'", field, node.element.name)),
+        }
+    }
+}
+
+/// Physical value of a field, inverse of `convert`
+fn unconvert(field: &Field, element: &SchemaElement, logical: Option<Logical>) -> std::result::Result<Value, String> {
+    let type_ = element.type_.ok_or_else(|| format!("Column '{}' has no type", element.name))?;
+    let value = match *field {
+        Field::Boolean(v) => Value::Boolean(v),
+        Field::Int32(v) => Value::Int32(v),
+        Field::Int64(v) => Value::Int64(v),
+        Field::Float(v) if is_float16(element) => {
+            let bits = f32_to_float16(v);
+            Value::Bytes(vec![bits as u8, (bits >> 8) as u8])
+        },
+        Field::Float(v) => Value::Float(v),
+        Field::Double(v) => Value::Double(v),
+        Field::Bytes(ref v) | Field::Bson(ref v) => Value::Bytes(v.clone()),
+        Field::Str(ref v) | Field::Enum(ref v) | Field::Json(ref v) => Value::Bytes(v.clone().into_bytes()),
+        Field::Uuid(ref uuid) => Value::Bytes(uuid.0.to_vec()),
+        Field::Decimal(ref decimal) => match logical {
+            Some(Logical::Decimal { precision, scale }) if (precision, scale) == (decimal.precision(), decimal.scale()) =>
+                decimal.to_value(type_, element.type_length.unwrap_or(0))?,
+            _ => return Err(format!("Decimal {} does not match column '{}'", decimal, element.name)),
+        },
+        Field::Temporal(ref temporal) => temporal_type(element)?.
+            ok_or_else(|| format!("Column '{}' is not temporal", element.name))?.
+            encode(temporal, type_)?,
+        _ => return Err(format!("Field {:?} is not a value of column '{}'", field, element.name)),
+    };
+
+    if !value.matches(type_, element.type_length.unwrap_or(-1)) {
+        return Err(format!("Field {:?} does not match column '{}' of type {:?}", field, element.name, type_))
+    }
+    Ok(value)
+}
+
+/// Shred records into levels and values of every leaf column
+pub fn shred(schema: &[SchemaElement], rows: &[Row]) -> std::result::Result<Vec<ColumnData>, String> {
+    let mut shredder = Shredder::new(schema)?;
+    for row in rows {
+        shredder.write_row(row)?;
+    }
+    Ok(shredder.take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use schema::Schema;
+    use temporal::Unit;
+
+    fn group(name: &str, repetition: Option<FieldRepetitionType>, children: i32, converted: Option<ConvertedType>) -> SchemaElement {
+        SchemaElement::new(None, None, repetition, name.to_string(), children, converted, None, None, None, None)
+    }
+
+    fn leaf(name: &str, repetition: FieldRepetitionType, type_: Type, converted: Option<ConvertedType>) -> SchemaElement {
+        SchemaElement::new(type_, None, repetition, name.to_string(), None, converted, None, None, None, None)
+    }
+
+    fn column(rep_levels: Vec<i16>, def_levels: Vec<i16>, values: Vec<Value>) -> ColumnData {
+        ColumnData { rep_levels, def_levels, values }
+    }
+
+    fn ints(values: &[i32]) -> Vec<Value> {
+        values.iter().map(|&v| Value::Int32(v)).collect()
+    }
+
+    fn int_list(values: &[i32]) -> Field {
+        Field::List(values.iter().map(|&v| Field::Int32(v)).collect())
+    }
+
+    #[test]
+    fn three_level_list() {
+        use parquet::FieldRepetitionType::*;
+        let schema = vec![
+            group("schema", None, 1, None),
+            group("numbers", Some(OPTIONAL), 1, Some(ConvertedType::LIST)),
+            group("list", Some(REPEATED), 1, None),
+            leaf("element", OPTIONAL, Type::INT32, None),
+        ];
+        let root = build_tree(&schema).unwrap();
+
+        // [1, null, 2], null, [], [3]
+        let data = column(vec![0, 1, 1, 0, 0, 0], vec![3, 2, 3, 0, 1, 3], ints(&[1, 2, 3]));
+        let rows = assemble(&root, vec![data]).unwrap();
+        let numbers: Vec<&Field> = rows.iter().map(|r| r.get("numbers").unwrap()).collect();
+        assert_eq!(vec![
+            &Field::List(vec![Field::Int32(1), Field::Null, Field::Int32(2)]),
+            &Field::Null,
+            &Field::List(vec![]),
+            &int_list(&[3]),
+        ], numbers);
+    }
+
+    #[test]
+    fn legacy_two_level_lists() {
+        use parquet::FieldRepetitionType::*;
+        let schema = vec![
+            group("schema", None, 3, None),
+            // repeated primitive inside LIST
+            group("a", Some(REQUIRED), 1, Some(ConvertedType::LIST)),
+            leaf("a_elem", REPEATED, Type::INT32, None),
+            // Avro style "array" struct
+            group("b", Some(REQUIRED), 1, Some(ConvertedType::LIST)),
+            group("array", Some(REPEATED), 1, None),
+            leaf("x", REQUIRED, Type::INT32, None),
+            // unannotated repeated primitive
+            leaf("c", REPEATED, Type::INT32, None),
+        ];
+        let root = build_tree(&schema).unwrap();
+
+        let a = column(vec![0, 1], vec![1, 1], ints(&[1, 2]));
+        let b = column(vec![0], vec![1], ints(&[7]));
+        let c = column(vec![0], vec![0], vec![]);
+        let rows = assemble(&root, vec![a, b, c]).unwrap();
+
+        assert_eq!(1, rows.len());
+        assert_eq!(Some(&int_list(&[1, 2])), rows[0].get("a"));
+        let element = Field::Group(Row { fields: vec![("x".to_string(), Field::Int32(7))] });
+        assert_eq!(Some(&Field::List(vec![element])), rows[0].get("b"));
+        assert_eq!(Some(&Field::List(vec![])), rows[0].get("c"));
+    }
+
+    #[test]
+    fn map_of_strings() {
+        use parquet::FieldRepetitionType::*;
+        let schema = vec![
+            group("schema", None, 1, None),
+            group("attrs", Some(OPTIONAL), 1, Some(ConvertedType::MAP)),
+            group("key_value", Some(REPEATED), 2, Some(ConvertedType::MAP_KEY_VALUE)),
+            leaf("key", REQUIRED, Type::BYTE_ARRAY, Some(ConvertedType::UTF8)),
+            leaf("value", OPTIONAL, Type::INT32, None),
+        ];
+        let root = build_tree(&schema).unwrap();
+
+        // {"a": 1, "b": null}, {}
+        let keys = column(vec![0, 1, 0], vec![2, 2, 1], vec![Value::Bytes(b"a".to_vec()), Value::Bytes(b"b".to_vec())]);
+        let values = column(vec![0, 1, 0], vec![3, 2, 1], ints(&[1]));
+        let rows = assemble(&root, vec![keys, values]).unwrap();
+
+        assert_eq!(Some(&Field::Map(vec![
+            (Field::Str("a".to_string()), Field::Int32(1)),
+            (Field::Str("b".to_string()), Field::Null),
+        ])), rows[0].get("attrs"));
+        assert_eq!(Some(&Field::Map(vec![])), rows[1].get("attrs"));
+    }
+
+    #[test]
+    fn tagged_logical_types() {
+        use parquet::FieldRepetitionType::*;
+        let mut uuid = leaf("id", REQUIRED, Type::FIXED_LEN_BYTE_ARRAY, None);
+        uuid.type_length = Some(16);
+        uuid.logical_type = Some(LogicalType::UUID(UUIDType::new()));
+        let schema = vec![
+            group("schema", None, 4, None),
+            uuid,
+            leaf("kind", REQUIRED, Type::BYTE_ARRAY, Some(ConvertedType::ENUM)),
+            leaf("payload", REQUIRED, Type::BYTE_ARRAY, Some(ConvertedType::JSON)),
+            leaf("doc", REQUIRED, Type::BYTE_ARRAY, Some(ConvertedType::BSON)),
+        ];
+        let root = build_tree(&schema).unwrap();
+
+        let bytes = |b: &[u8]| column(vec![0], vec![0], vec![Value::Bytes(b.to_vec())]);
+        let rows = assemble(&root, vec![bytes(&[1; 16]), bytes(b"CLICK"), bytes(b"{\"a\": 1}"), bytes(&[5, 0, 0, 0, 0])]).unwrap();
+
+        assert_eq!(Some(&Field::Uuid(Uuid([1; 16]))), rows[0].get("id"));
+        assert_eq!(Some(&Field::Enum("CLICK".to_string())), rows[0].get("kind"));
+        assert_eq!(Some(&Field::Json("{\"a\": 1}".to_string())), rows[0].get("payload"));
+        assert_eq!(Some(&Field::Bson(vec![5, 0, 0, 0, 0])), rows[0].get("doc"));
+    }
+
+    #[cfg(feature = "serde_json")]
+    #[test]
+    fn parse_json() {
+        let json = Field::Json("{\"a\": [1, 2]}".to_string()).parse_json().unwrap().unwrap();
+        assert_eq!(2, json["a"][1]);
+        assert!(Field::Json("{".to_string()).parse_json().unwrap().is_err());
+        assert!(Field::Str("{}".to_string()).parse_json().is_none());
+    }
+
+    #[test]
+    fn nested_lists() {
+        use parquet::FieldRepetitionType::*;
+        let schema = vec![
+            group("schema", None, 1, None),
+            group("matrix", Some(REQUIRED), 1, Some(ConvertedType::LIST)),
+            group("list", Some(REPEATED), 1, None),
+            group("element", Some(REQUIRED), 1, Some(ConvertedType::LIST)),
+            group("list", Some(REPEATED), 1, None),
+            leaf("element", REQUIRED, Type::INT32, None),
+        ];
+        let root = build_tree(&schema).unwrap();
+
+        // [[1, 2], [], [3]]
+        let data = column(vec![0, 2, 1, 1], vec![2, 2, 1, 2], ints(&[1, 2, 3]));
+        let rows = assemble(&root, vec![data]).unwrap();
+        assert_eq!(Some(&Field::List(vec![int_list(&[1, 2]), int_list(&[]), int_list(&[3])])), rows[0].get("matrix"));
+    }
+
+    #[test]
+    fn nested_columns() {
+        use parquet::FieldRepetitionType::*;
+        let schema = vec![
+            group("schema", None, 4, None),
+            leaf("id", REQUIRED, Type::INT32, None),
+            group("numbers", Some(OPTIONAL), 1, Some(ConvertedType::LIST)),
+            group("list", Some(REPEATED), 1, None),
+            leaf("element", OPTIONAL, Type::INT32, None),
+            group("matrix", Some(REQUIRED), 1, Some(ConvertedType::LIST)),
+            group("list", Some(REPEATED), 1, None),
+            group("element", Some(REQUIRED), 1, Some(ConvertedType::LIST)),
+            // legacy 2-level inner list
+            leaf("element", REPEATED, Type::INT32, None),
+            group("attrs", Some(OPTIONAL), 1, Some(ConvertedType::MAP)),
+            group("key_value", Some(REPEATED), 2, Some(ConvertedType::MAP_KEY_VALUE)),
+            leaf("key", REQUIRED, Type::BYTE_ARRAY, Some(ConvertedType::UTF8)),
+            leaf("value", OPTIONAL, Type::INT32, None),
+        ];
+        let root = build_tree(&schema).unwrap();
+        let some_ints = |values: &[i32]| values.iter().map(|&v| Some(Field::Int32(v))).collect::<Vec<_>>();
+
+        let id = nest(&root, 0, column(vec![0, 0], vec![0, 0], ints(&[1, 2]))).unwrap();
+        assert_eq!(NestedColumn { offsets: vec![], validity: vec![], values: some_ints(&[1, 2]) }, id);
+
+        // [1, null, 2], null, [], [3]
+        let numbers = nest(&root, 1, column(vec![0, 1, 1, 0, 0, 0], vec![3, 2, 3, 0, 1, 3], ints(&[1, 2, 3]))).unwrap();
+        assert_eq!(vec![vec![0, 3, 3, 3, 4]], numbers.offsets);
+        assert_eq!(vec![vec![true, false, true, true]], numbers.validity);
+        assert_eq!(vec![Some(Field::Int32(1)), None, Some(Field::Int32(2)), Some(Field::Int32(3))], numbers.values);
+
+        // [[1, 2], [], [3]], []
+        let matrix = nest(&root, 2, column(vec![0, 2, 1, 1, 0], vec![2, 2, 1, 2, 0], ints(&[1, 2, 3]))).unwrap();
+        assert_eq!(vec![vec![0, 3, 3], vec![0, 2, 2, 3]], matrix.offsets);
+        assert_eq!(vec![vec![true, true], vec![true; 3]], matrix.validity);
+        assert_eq!(some_ints(&[1, 2, 3]), matrix.values);
+
+        // {"a": 1, "b": null}, null
+        let keys = nest(&root, 3, column(vec![0, 1, 0], vec![2, 2, 0], vec![Value::Bytes(b"a".to_vec()), Value::Bytes(b"b".to_vec())])).unwrap();
+        let values = nest(&root, 4, column(vec![0, 1, 0], vec![3, 2, 0], ints(&[1]))).unwrap();
+        assert_eq!((&vec![vec![0, 2, 2]], &vec![vec![true, false]]), (&keys.offsets, &keys.validity));
+        assert_eq!((&keys.offsets, &keys.validity), (&values.offsets, &values.validity));
+        assert_eq!(vec![Some(Field::Str("a".to_string())), Some(Field::Str("b".to_string()))], keys.values);
+        assert_eq!(vec![Some(Field::Int32(1)), None], values.values);
+
+        assert!(nest(&root, 5, column(vec![0], vec![0], vec![])).is_err());
+        assert!(nest(&root, 1, column(vec![0], vec![3], vec![])).is_err());
+    }
+
+    fn row(fields: Vec<(&str, Field)>) -> Row {
+        Row { fields: fields.into_iter().map(|(name, field)| (name.to_string(), field)).collect() }
+    }
+
+    #[test]
+    fn shred_nested_records() {
+        let schema = Schema::message("m").
+            required_int64("id").
+            optional_group("user", |g| g.required_string("name").repeated_int32("scores")).
+            optional_list("tags", |e| e.optional_string("element")).
+            optional_map("attrs", |kv| kv.required_string("key").optional_int32("value")).
+            build().unwrap();
+        let str = |s: &str| Field::Str(s.to_string());
+        let rows = vec![
+            row(vec![
+                ("id", Field::Int64(1)),
+                ("user", Field::Group(row(vec![("name", str("a")), ("scores", int_list(&[1, 2]))]))),
+                ("tags", Field::List(vec![str("x"), Field::Null, str("y")])),
+                ("attrs", Field::Map(vec![(str("k"), Field::Int32(1)), (str("n"), Field::Null)])),
+            ]),
+            row(vec![
+                ("id", Field::Int64(2)),
+                ("user", Field::Null),
+                ("tags", Field::List(vec![])),
+                ("attrs", Field::Null),
+            ]),
+            row(vec![
+                ("id", Field::Int64(3)),
+                ("user", Field::Group(row(vec![("name", str("c")), ("scores", int_list(&[]))]))),
+                ("tags", Field::Null),
+                ("attrs", Field::Map(vec![])),
+            ]),
+        ];
+
+        let data = shred(&schema, &rows).unwrap();
+        // user.scores
+        assert_eq!(column(vec![0, 1, 0, 0], vec![2, 2, 0, 1], ints(&[1, 2])), data[2]);
+        // tags.list.element
+        assert_eq!((vec![0, 1, 1, 0, 0], vec![3, 2, 3, 1, 0]), (data[3].rep_levels.clone(), data[3].def_levels.clone()));
+        assert_eq!(rows, assemble(&build_tree(&schema).unwrap(), data).unwrap());
+    }
+
+    #[test]
+    fn shred_invalid_records() {
+        let schema = Schema::message("m").required_int32("a").optional_list("b", |e| e.required_int32("element")).
+            build().unwrap();
+        let mut shredder = Shredder::new(&schema).unwrap();
+        shredder.write_row(&row(vec![("a", Field::Int32(1)), ("b", int_list(&[1]))])).unwrap();
+        // nothing of rejected records remains
+        assert!(shredder.write_row(&row(vec![("a", Field::Int32(2)), ("b", Field::List(vec![Field::Null]))])).is_err());
+        assert!(shredder.write_row(&row(vec![("a", Field::Null)])).is_err());
+        assert!(shredder.write_row(&row(vec![("a", Field::Int32(2)), ("c", Field::Null)])).is_err());
+        assert!(shredder.write_row(&row(vec![("a", Field::Int64(2))])).is_err());
+        assert!(shredder.write_row(&row(vec![("a", Field::Int32(2))])).is_ok());
+        assert_eq!((2, 12), (shredder.num_rows(), shredder.values_size()));
+
+        let data = shredder.take();
+        assert_eq!(ints(&[1, 2]), data[0].values);
+        assert_eq!(column(vec![0, 0], vec![2, 0], ints(&[1])), data[1]);
+        assert_eq!(0, shredder.num_rows());
+    }
+
+    #[test]
+    fn shred_logical_types() {
+        let schema = Schema::message("m").
+            fixed_len_byte_array(FieldRepetitionType::REQUIRED, 16, "u").logical(LogicalType::UUID(UUIDType::new())).
+            required_int64("d").logical(LogicalType::DECIMAL(DecimalType::new(2, 10))).
+            required_int32("day").logical(LogicalType::DATE(DateType::new())).
+            fixed_len_byte_array(FieldRepetitionType::REQUIRED, 2, "h").logical(LogicalType::FLOAT16(Float16Type::new())).
+            required_int96("ts").
+            build().unwrap();
+        let row = row(vec![
+            ("u", Field::Uuid(Uuid([7; 16]))),
+            ("d", Field::Decimal(Decimal::new(-12345, 10, 2).unwrap())),
+            ("day", Field::Temporal(Temporal::Date(18_000))),
+            ("h", Field::Float(-2.5)),
+            ("ts", Field::Temporal(Temporal::Timestamp { value: 1, unit: Unit::Nanos, adjusted_to_utc: true })),
+        ]);
+        let data = shred(&schema, ::std::slice::from_ref(&row)).unwrap();
+        assert_eq!(vec![Value::Int64(-12345)], data[1].values);
+        assert_eq!(vec![Value::Bytes(vec![0x00, 0xc1])], data[3].values);
+        assert_eq!(vec![row.clone()], assemble(&build_tree(&schema).unwrap(), data).unwrap());
+
+        let mut other_scale = row;
+        other_scale.fields[1].1 = Field::Decimal(Decimal::new(1, 10, 3).unwrap());
+        assert!(shred(&schema, &[other_scale]).is_err());
+    }
+}
@@ -0,0 +1,1085 @@
+use parquet::*;
+use logical::{self, Logical};
+use decimal::decimal_params;
+
+/// Leaf column of the schema, listed in the same order as column chunks in a row group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColumnDescriptor {
+    pub path: Vec<String>,
+    pub type_: Type,
+    pub type_length: Option<i32>,
+    pub max_def_level: i16,
+    pub max_rep_level: i16,
+    /// Position of the leaf element in `FileMetaData.schema`
+    pub schema_idx: usize,
+}
+
+impl ColumnDescriptor {
+    pub fn path_string(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// Walk flattened schema in depth-first order and collect leaf columns with their levels.
+/// First element is the root and does not contribute to path or levels.
+pub fn leaf_columns(schema: &[SchemaElement]) -> Result<Vec<ColumnDescriptor>, String> {
+    if schema.is_empty() { return Err("Schema is empty".to_string()) }
+
+    let mut columns = Vec::new();
+    // (remaining children, def level, rep level) of the groups being walked
+    let mut stack: Vec<(i32, i16, i16)> = vec![(schema[0].num_children.unwrap_or(0), 0, 0)];
+    let mut path: Vec<String> = Vec::new();
+
+    for (idx, element) in schema.iter().enumerate().skip(1) {
+        // pop finished groups
+        while stack.last().is_some_and(|&(remaining, _, _)| remaining == 0) {
+            stack.pop();
+            path.pop();
+        }
+        let (def_level, rep_level) = match stack.last_mut() {
+            Some(parent) => {
+                parent.0 -= 1;
+                (parent.1, parent.2)
+            },
+            None => return Err(format!("Schema element '{}' is outside of the root group", element.name)),
+        };
+
+        let (def_level, rep_level) = match element.repetition_type {
+            Some(FieldRepetitionType::OPTIONAL) => (def_level + 1, rep_level),
+            Some(FieldRepetitionType::REPEATED) => (def_level + 1, rep_level + 1),
+            _ => (def_level, rep_level),
+        };
+
+        match element.num_children {
+            Some(children) if children > 0 || element.type_.is_none() => {
+                stack.push((children, def_level, rep_level));
+                path.push(element.name.clone());
+            },
+            _ => {
+                let type_ = element.type_.
+                    ok_or_else(|| format!("Leaf '{}' does not have a type", element.name))?;
+                let mut column_path = path.clone();
+                column_path.push(element.name.clone());
+                columns.push(ColumnDescriptor {
+                    path: column_path,
+                    type_,
+                    type_length: element.type_length,
+                    max_def_level: def_level,
+                    max_rep_level: rep_level,
+                    schema_idx: idx,
+                });
+            }
+        }
+    }
+
+    Ok(columns)
+}
+
+/// Find leaf column index by dot-separated path, e.g. "a.b.c"
+pub fn find_column(columns: &[ColumnDescriptor], path: &str) -> Option<usize> {
+    columns.iter().position(|c| c.path_string() == path)
+}
+
+/// Render schema in the text format of parquet-mr, e.g.
+///
+/// ```text
+/// message schema {
+///   required int64 id = 1;
+///   optional group tags (LIST) {
+///     repeated group list {
+///       optional binary element (STRING);
+///     }
+///   }
+/// }
+/// ```
+///
+/// Elements with `LogicalType` are annotated with its name, otherwise with legacy `ConvertedType`.
+pub fn print_schema(schema: &[SchemaElement]) -> Result<String, String> {
+    let root = schema.first().ok_or_else(|| "Schema is empty".to_string())?;
+    let mut out = format!("message {}", root.name);
+    let mut pos = 1;
+    print_children(schema, &mut pos, root.num_children.unwrap_or(0), 1, &mut out)?;
+    if pos != schema.len() {
+        return Err(format!("Schema element '{}' is outside of the root group", schema[pos].name))
+    }
+    out.push('\n');
+    Ok(out)
+}
+
+/// Print ` {`, `count` elements starting at `pos` and closing brace
+fn print_children(schema: &[SchemaElement], pos: &mut usize, count: i32, depth: usize, out: &mut String) -> Result<(), String> {
+    out.push_str(" {\n");
+    for _ in 0..count {
+        let element = schema.get(*pos).ok_or_else(|| "Schema ends in the middle of a group".to_string())?;
+        *pos += 1;
+
+        out.push_str(&"  ".repeat(depth));
+        let repetition = match element.repetition_type {
+            Some(FieldRepetitionType::REQUIRED) => "required",
+            Some(FieldRepetitionType::OPTIONAL) => "optional",
+            Some(FieldRepetitionType::REPEATED) => "repeated",
+            None => return Err(format!("Element '{}' does not have repetition type", element.name)),
+        };
+        let type_ = match element.type_ {
+            None => "group".to_string(),
+            Some(Type::BOOLEAN) => "boolean".to_string(),
+            Some(Type::INT32) => "int32".to_string(),
+            Some(Type::INT64) => "int64".to_string(),
+            Some(Type::INT96) => "int96".to_string(),
+            Some(Type::FLOAT) => "float".to_string(),
+            Some(Type::DOUBLE) => "double".to_string(),
+            Some(Type::BYTE_ARRAY) => "binary".to_string(),
+            Some(Type::FIXED_LEN_BYTE_ARRAY) => format!("fixed_len_byte_array({})",
+                element.type_length.ok_or_else(|| format!("Element '{}' does not have type length", element.name))?),
+        };
+        out.push_str(&format!("{} {} {}", repetition, type_, element.name));
+        if let Some(annotation) = annotation(element) {
+            out.push_str(&format!(" ({})", annotation));
+        }
+        if let Some(field_id) = element.field_id {
+            out.push_str(&format!(" = {}", field_id));
+        }
+
+        if element.type_.is_none() {
+            print_children(schema, pos, element.num_children.unwrap_or(0), depth + 1, out)?;
+        } else {
+            out.push_str(";\n");
+        }
+    }
+    out.push_str(&"  ".repeat(depth - 1));
+    out.push('}');
+    if depth > 1 {
+        out.push('\n');
+    }
+    Ok(())
+}
+
+fn annotation(element: &SchemaElement) -> Option<String> {
+    let unit = |unit: &TimeUnit| match *unit {
+        TimeUnit::MILLIS(_) => "MILLIS",
+        TimeUnit::MICROS(_) => "MICROS",
+        TimeUnit::NANOS(_) => "NANOS",
+    };
+
+    if let Some(ref logical_type) = element.logical_type {
+        return Some(match *logical_type {
+            LogicalType::STRING(_) => "STRING".to_string(),
+            LogicalType::MAP(_) => "MAP".to_string(),
+            LogicalType::LIST(_) => "LIST".to_string(),
+            LogicalType::ENUM(_) => "ENUM".to_string(),
+            LogicalType::DECIMAL(ref d) => format!("DECIMAL({},{})", d.precision, d.scale),
+            LogicalType::DATE(_) => "DATE".to_string(),
+            LogicalType::TIME(ref t) => format!("TIME({},{})", unit(&t.unit), t.is_adjusted_to_u_t_c),
+            LogicalType::TIMESTAMP(ref t) => format!("TIMESTAMP({},{})", unit(&t.unit), t.is_adjusted_to_u_t_c),
+            LogicalType::INTEGER(ref i) => format!("INTEGER({},{})", i.bit_width, i.is_signed),
+            LogicalType::UNKNOWN(_) => "UNKNOWN".to_string(),
+            LogicalType::JSON(_) => "JSON".to_string(),
+            LogicalType::BSON(_) => "BSON".to_string(),
+            LogicalType::UUID(_) => "UUID".to_string(),
+            LogicalType::FLOAT16(_) => "FLOAT16".to_string(),
+        })
+    }
+
+    element.converted_type.map(|converted_type| match converted_type {
+        ConvertedType::DECIMAL => format!("DECIMAL({},{})", element.precision.unwrap_or(0), element.scale.unwrap_or(0)),
+        _ => format!("{:?}", converted_type),
+    })
+}
+
+/// Parse schema in the text format of `print_schema` into flattened schema elements.
+/// Annotations of new logical types set both `logical_type` and the equivalent `converted_type`,
+/// legacy names like `UTF8` or `TIMESTAMP_MILLIS` set only `converted_type`.
+pub fn parse_schema(text: &str) -> Result<Vec<SchemaElement>, String> {
+    let mut parser = Parser { tokens: tokenize(text), pos: 0 };
+    parser.expect("message")?;
+    let name = parser.name()?;
+
+    let mut schema = vec![SchemaElement::new(None, None, None, name, None, None, None, None, None, None)];
+    schema[0].num_children = Some(parser.fields(&mut schema)?);
+    if let Some(&(ref token, line)) = parser.tokens.get(parser.pos) {
+        return Err(format!("Unexpected '{}' after the message at line {}", token, line))
+    }
+    Ok(schema)
+}
+
+/// Split text into names, numbers and punctuation, each with its line number
+fn tokenize(text: &str) -> Vec<(String, usize)> {
+    let mut tokens = vec![];
+    for (line_idx, line) in text.lines().enumerate() {
+        let mut token = String::new();
+        for c in line.chars() {
+            if c.is_whitespace() || "{}();=,".contains(c) {
+                if !token.is_empty() {
+                    tokens.push((token.clone(), line_idx + 1));
+                    token.clear();
+                }
+                if !c.is_whitespace() {
+                    tokens.push((c.to_string(), line_idx + 1));
+                }
+            } else {
+                token.push(c);
+            }
+        }
+        if !token.is_empty() {
+            tokens.push((token, line_idx + 1));
+        }
+    }
+    tokens
+}
+
+struct Parser {
+    tokens: Vec<(String, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|t| t.0.as_str())
+    }
+
+    fn next(&mut self) -> Result<(String, usize), String> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| "Unexpected end of schema".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        let (token, line) = self.next()?;
+        if token.eq_ignore_ascii_case(expected) { Ok(()) } else {
+            Err(format!("Expected '{}' but got '{}' at line {}", expected, token, line))
+        }
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let (token, line) = self.next()?;
+        if token.len() == 1 && "{}();=,".contains(token.as_str()) {
+            return Err(format!("Expected a name but got '{}' at line {}", token, line))
+        }
+        Ok(token)
+    }
+
+    fn number<T: ::std::str::FromStr>(&mut self) -> Result<T, String> {
+        let (token, line) = self.next()?;
+        token.parse().map_err(|_| format!("Expected a number but got '{}' at line {}", token, line))
+    }
+
+    fn boolean(&mut self) -> Result<bool, String> {
+        let (token, line) = self.next()?;
+        token.to_lowercase().parse().map_err(|_| format!("Expected true or false but got '{}' at line {}", token, line))
+    }
+
+    /// Parse `{ field* }` appending elements to `schema`, returns number of fields
+    fn fields(&mut self, schema: &mut Vec<SchemaElement>) -> Result<i32, String> {
+        self.expect("{")?;
+        let mut count = 0;
+        while self.peek() != Some("}") {
+            self.field(schema)?;
+            count += 1;
+        }
+        self.expect("}")?;
+        Ok(count)
+    }
+
+    fn field(&mut self, schema: &mut Vec<SchemaElement>) -> Result<(), String> {
+        let (repetition, line) = self.next()?;
+        let repetition = match repetition.to_lowercase().as_str() {
+            "required" => FieldRepetitionType::REQUIRED,
+            "optional" => FieldRepetitionType::OPTIONAL,
+            "repeated" => FieldRepetitionType::REPEATED,
+            _ => return Err(format!("Expected repetition but got '{}' at line {}", repetition, line)),
+        };
+
+        let (type_, line) = self.next()?;
+        let type_ = match type_.to_lowercase().as_str() {
+            "group" => None,
+            "boolean" => Some(Type::BOOLEAN),
+            "int32" => Some(Type::INT32),
+            "int64" => Some(Type::INT64),
+            "int96" => Some(Type::INT96),
+            "float" => Some(Type::FLOAT),
+            "double" => Some(Type::DOUBLE),
+            "binary" => Some(Type::BYTE_ARRAY),
+            "fixed_len_byte_array" => Some(Type::FIXED_LEN_BYTE_ARRAY),
+            _ => return Err(format!("Unknown type '{}' at line {}", type_, line)),
+        };
+
+        let mut element = SchemaElement::new(type_, None, repetition, String::new(), None, None, None, None, None, None);
+        if type_ == Some(Type::FIXED_LEN_BYTE_ARRAY) {
+            self.expect("(")?;
+            element.type_length = Some(self.number()?);
+            self.expect(")")?;
+        }
+        element.name = self.name()?;
+
+        if self.peek() == Some("(") {
+            self.next()?;
+            self.annotation(&mut element)?;
+            self.expect(")")?;
+        }
+        if self.peek() == Some("=") {
+            self.next()?;
+            element.field_id = Some(self.number()?);
+        }
+
+        if type_.is_none() {
+            let idx = schema.len();
+            schema.push(element);
+            schema[idx].num_children = Some(self.fields(schema)?);
+        } else {
+            self.expect(";")?;
+            schema.push(element);
+        }
+        Ok(())
+    }
+
+    fn annotation(&mut self, element: &mut SchemaElement) -> Result<(), String> {
+        let (name, line) = self.next()?;
+        let name = name.to_uppercase();
+
+        let unit = |parser: &mut Parser| -> Result<TimeUnit, String> {
+            let (unit, line) = parser.next()?;
+            match unit.to_uppercase().as_str() {
+                "MILLIS" => Ok(TimeUnit::MILLIS(MilliSeconds::new())),
+                "MICROS" => Ok(TimeUnit::MICROS(MicroSeconds::new())),
+                "NANOS" => Ok(TimeUnit::NANOS(NanoSeconds::new())),
+                _ => Err(format!("Unknown time unit '{}' at line {}", unit, line)),
+            }
+        };
+
+        let logical_type = match name.as_str() {
+            "STRING" => LogicalType::STRING(StringType::new()),
+            "MAP" => LogicalType::MAP(MapType::new()),
+            "LIST" => LogicalType::LIST(ListType::new()),
+            "ENUM" => LogicalType::ENUM(EnumType::new()),
+            "DATE" => LogicalType::DATE(DateType::new()),
+            "JSON" => LogicalType::JSON(JsonType::new()),
+            "BSON" => LogicalType::BSON(BsonType::new()),
+            "UUID" => LogicalType::UUID(UUIDType::new()),
+            "FLOAT16" => LogicalType::FLOAT16(Float16Type::new()),
+            "UNKNOWN" => LogicalType::UNKNOWN(NullType::new()),
+            "DECIMAL" => {
+                self.expect("(")?;
+                let precision = self.number()?;
+                self.expect(",")?;
+                let scale = self.number()?;
+                self.expect(")")?;
+                LogicalType::DECIMAL(DecimalType::new(scale, precision))
+            },
+            "TIME" | "TIMESTAMP" => {
+                self.expect("(")?;
+                let unit = unit(self)?;
+                self.expect(",")?;
+                let adjusted_to_utc = self.boolean()?;
+                self.expect(")")?;
+                if name == "TIME" {
+                    LogicalType::TIME(TimeType::new(adjusted_to_utc, unit))
+                } else {
+                    LogicalType::TIMESTAMP(TimestampType::new(adjusted_to_utc, unit))
+                }
+            },
+            "INTEGER" => {
+                self.expect("(")?;
+                let bit_width = self.number()?;
+                self.expect(",")?;
+                let signed = self.boolean()?;
+                self.expect(")")?;
+                if ![8, 16, 32, 64].contains(&bit_width) {
+                    return Err(format!("Invalid INTEGER bit width {} at line {}", bit_width, line))
+                }
+                LogicalType::INTEGER(IntType::new(bit_width, signed))
+            },
+            _ => {
+                element.converted_type = Some(legacy_converted_type(&name).
+                    ok_or_else(|| format!("Unknown annotation '{}' at line {}", name, line))?);
+                return Ok(())
+            },
+        };
+
+        set_logical_type(element, logical_type);
+        Ok(())
+    }
+}
+
+/// Annotate element with logical type and its equivalent converted type, if there is one,
+/// so that readers which know only converted types understand the column too.
+fn set_logical_type(element: &mut SchemaElement, logical_type: LogicalType) {
+    let int = |bit_width: i8, signed: bool| match (bit_width, signed) {
+        (8, true) => Some(ConvertedType::INT_8),
+        (16, true) => Some(ConvertedType::INT_16),
+        (32, true) => Some(ConvertedType::INT_32),
+        (64, true) => Some(ConvertedType::INT_64),
+        (8, false) => Some(ConvertedType::UINT_8),
+        (16, false) => Some(ConvertedType::UINT_16),
+        (32, false) => Some(ConvertedType::UINT_32),
+        (64, false) => Some(ConvertedType::UINT_64),
+        _ => None,
+    };
+
+    element.converted_type = match logical_type {
+        LogicalType::STRING(_) => Some(ConvertedType::UTF8),
+        LogicalType::MAP(_) => Some(ConvertedType::MAP),
+        LogicalType::LIST(_) => Some(ConvertedType::LIST),
+        LogicalType::ENUM(_) => Some(ConvertedType::ENUM),
+        LogicalType::DECIMAL(ref d) => {
+            element.precision = Some(d.precision);
+            element.scale = Some(d.scale);
+            Some(ConvertedType::DECIMAL)
+        },
+        LogicalType::DATE(_) => Some(ConvertedType::DATE),
+        // legacy time types are UTC-adjusted and have no nanosecond variants
+        LogicalType::TIME(ref t) => match t.unit {
+            TimeUnit::MILLIS(_) if t.is_adjusted_to_u_t_c => Some(ConvertedType::TIME_MILLIS),
+            TimeUnit::MICROS(_) if t.is_adjusted_to_u_t_c => Some(ConvertedType::TIME_MICROS),
+            _ => None,
+        },
+        LogicalType::TIMESTAMP(ref t) => match t.unit {
+            TimeUnit::MILLIS(_) if t.is_adjusted_to_u_t_c => Some(ConvertedType::TIMESTAMP_MILLIS),
+            TimeUnit::MICROS(_) if t.is_adjusted_to_u_t_c => Some(ConvertedType::TIMESTAMP_MICROS),
+            _ => None,
+        },
+        LogicalType::INTEGER(ref i) => int(i.bit_width, i.is_signed),
+        LogicalType::JSON(_) => Some(ConvertedType::JSON),
+        LogicalType::BSON(_) => Some(ConvertedType::BSON),
+        LogicalType::UNKNOWN(_) | LogicalType::UUID(_) | LogicalType::FLOAT16(_) => None,
+    };
+    element.logical_type = Some(logical_type);
+}
+
+/// Converted types which do not share their name with a logical type
+fn legacy_converted_type(name: &str) -> Option<ConvertedType> {
+    Some(match name {
+        "UTF8" => ConvertedType::UTF8,
+        "MAP_KEY_VALUE" => ConvertedType::MAP_KEY_VALUE,
+        "TIME_MILLIS" => ConvertedType::TIME_MILLIS,
+        "TIME_MICROS" => ConvertedType::TIME_MICROS,
+        "TIMESTAMP_MILLIS" => ConvertedType::TIMESTAMP_MILLIS,
+        "TIMESTAMP_MICROS" => ConvertedType::TIMESTAMP_MICROS,
+        "UINT_8" => ConvertedType::UINT_8,
+        "UINT_16" => ConvertedType::UINT_16,
+        "UINT_32" => ConvertedType::UINT_32,
+        "UINT_64" => ConvertedType::UINT_64,
+        "INT_8" => ConvertedType::INT_8,
+        "INT_16" => ConvertedType::INT_16,
+        "INT_32" => ConvertedType::INT_32,
+        "INT_64" => ConvertedType::INT_64,
+        "INTERVAL" => ConvertedType::INTERVAL,
+        _ => return None,
+    })
+}
+
+/// Check schema against the rules of the spec: tree structure and `num_children`, required attributes,
+/// decimal precision, logical type and physical type pairing, LIST and MAP structure.
+/// Returns all problems found, each prefixed with the dot-separated path of the element.
+pub fn validate_schema(schema: &[SchemaElement]) -> Result<(), Vec<String>> {
+    let root = match schema.first() {
+        Some(root) => root,
+        None => return Err(vec!["Schema is empty".to_string()]),
+    };
+
+    let mut validator = Validator { schema, root: &root.name, pos: 1, problems: vec![] };
+    if root.type_.is_some() {
+        validator.problems.push(format!("{}: root must be a group but has type {:?}", root.name, root.type_));
+    } else {
+        match root.num_children {
+            Some(children) if children > 0 => { validator.children(&[], children); },
+            _ => validator.problems.push(format!("{}: root must have at least one field", root.name)),
+        }
+    }
+    if validator.pos < schema.len() {
+        validator.problems.push(format!("{}: {} elements after the last field of the root",
+            root.name, schema.len() - validator.pos));
+    }
+
+    if validator.problems.is_empty() { Ok(()) } else { Err(validator.problems) }
+}
+
+struct Validator<'a> {
+    schema: &'a [SchemaElement],
+    root: &'a str,
+    /// Next element to visit
+    pos: usize,
+    problems: Vec<String>,
+}
+
+impl<'a> Validator<'a> {
+    /// Visit `count` fields of the group at `path`, returns indexes of those present
+    fn children(&mut self, path: &[&'a str], count: i32) -> Vec<usize> {
+        let mut fields = vec![];
+        for _ in 0..count {
+            let idx = self.pos;
+            let element = match self.schema.get(idx) {
+                Some(element) => element,
+                None => {
+                    let problem = format!("declares {} children but schema ends after {}", count, fields.len());
+                    self.problem(path, problem);
+                    break
+                },
+            };
+            self.pos += 1;
+
+            let mut field_path = path.to_vec();
+            field_path.push(&element.name);
+            if fields.iter().any(|&f: &usize| self.schema[f].name == element.name) {
+                self.problem(&field_path, "duplicate field name".to_string());
+            }
+            fields.push(idx);
+            self.field(&field_path, element);
+        }
+        fields
+    }
+
+    fn field(&mut self, path: &[&'a str], element: &'a SchemaElement) {
+        if element.repetition_type.is_none() {
+            self.problem(path, "missing repetition type".to_string());
+        }
+        if let Err(e) = logical::resolve(element) {
+            self.problem(path, e);
+        }
+
+        match element.type_ {
+            None => {
+                let children = match element.num_children {
+                    Some(children) if children > 0 => self.children(path, children),
+                    _ => {
+                        self.problem(path, "group must have at least one field".to_string());
+                        vec![]
+                    },
+                };
+                self.nested_type(path, element, &children);
+            },
+            Some(type_) => {
+                if element.num_children.is_some_and(|c| c > 0) {
+                    self.problem(path, format!("primitive {:?} can not have children", type_));
+                }
+                if type_ == Type::FIXED_LEN_BYTE_ARRAY && element.type_length.map_or(true, |len| len <= 0) {
+                    self.problem(path, format!("FIXED_LEN_BYTE_ARRAY needs positive type_length but has {:?}", element.type_length));
+                }
+                if let Ok(Some((precision, scale))) = decimal_params(element) {
+                    self.decimal(path, type_, element.type_length, precision, scale);
+                }
+            },
+        }
+    }
+
+    fn decimal(&mut self, path: &[&'a str], type_: Type, type_length: Option<i32>, precision: i32, scale: i32) {
+        let max_precision = match type_ {
+            Type::INT32 => Some(9),
+            Type::INT64 => Some(18),
+            // largest number of decimal digits in a signed integer of `len` bytes
+            Type::FIXED_LEN_BYTE_ARRAY => type_length.filter(|&len| len > 0).
+                map(|len| ((8 * len - 1) as f64 * 2_f64.log10()).floor() as i32),
+            _ => None,
+        };
+
+        if precision <= 0 {
+            self.problem(path, format!("decimal precision must be positive but is {}", precision));
+        }
+        if scale < 0 || scale > precision {
+            self.problem(path, format!("decimal scale must be in 0..{} but is {}", precision, scale));
+        }
+        if let Some(max_precision) = max_precision {
+            if precision > max_precision {
+                self.problem(path, format!("decimal precision {} does not fit {:?} (at most {})", precision, type_, max_precision));
+            }
+        }
+    }
+
+    /// LIST and MAP structure, given the indexes of the group's fields
+    fn nested_type(&mut self, path: &[&'a str], element: &SchemaElement, children: &[usize]) {
+        let logical = match logical::resolve(element) {
+            Ok(Some(logical)) => logical,
+            _ => return,
+        };
+        let kind = match logical {
+            Logical::List => "LIST",
+            Logical::Map => "MAP",
+            // the repeated key-value group is checked with its map, some writers put it on the map itself
+            Logical::MapKeyValue if element.repetition_type != Some(FieldRepetitionType::REPEATED) => "MAP",
+            _ => return,
+        };
+
+        if element.repetition_type == Some(FieldRepetitionType::REPEATED) {
+            self.problem(path, format!("{} must be optional or required", kind));
+        }
+        let repeated = match children {
+            &[child] if self.schema[child].repetition_type == Some(FieldRepetitionType::REPEATED) => &self.schema[child],
+            _ => return self.problem(path, format!("{} must have a single repeated field", kind)),
+        };
+
+        if kind == "MAP" {
+            // the key is the first field of the repeated key-value group
+            let key = self.schema.get(children[0] + 1);
+            let key_values = repeated.num_children.unwrap_or(0);
+            if repeated.type_.is_some() || !(1..=2).contains(&key_values) {
+                self.problem(path, "MAP must contain a repeated group of key and optional value".to_string());
+            } else if key.map_or(true, |k| k.repetition_type != Some(FieldRepetitionType::REQUIRED)) {
+                self.problem(path, "MAP key must be required".to_string());
+            }
+        }
+    }
+
+    fn problem(&mut self, path: &[&str], problem: String) {
+        let path = if path.is_empty() { self.root.to_string() } else { path.join(".") };
+        self.problems.push(format!("{}: {}", path, problem));
+    }
+}
+
+/// Fluent builder of flattened schema elements, e.g.
+///
+/// ```ignore
+/// let schema = Schema::message("m").
+///     required_int64("id").field_id(1).
+///     optional_string("name").
+///     optional_group("addr", |g| g.required_binary("street").optional_int32("zip")).
+///     optional_list("tags", |e| e.optional_string("element")).
+///     build()?;
+/// ```
+///
+/// Modifiers like `logical` and `field_id` apply to the last added field.
+/// Problems are collected and reported by `build`.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    name: String,
+    /// Fields of this group, flattened in depth-first order
+    elements: Vec<SchemaElement>,
+    num_children: i32,
+    /// Position of the last added field in `elements`
+    last: Option<usize>,
+    errors: Vec<String>,
+}
+
+macro_rules! primitive_fields {
+    ($($type_:expr => $required:ident, $optional:ident, $repeated:ident;)*) => {
+        $(
+            pub fn $required(self, name: &str) -> Schema { self.primitive(FieldRepetitionType::REQUIRED, $type_, name) }
+            pub fn $optional(self, name: &str) -> Schema { self.primitive(FieldRepetitionType::OPTIONAL, $type_, name) }
+            pub fn $repeated(self, name: &str) -> Schema { self.primitive(FieldRepetitionType::REPEATED, $type_, name) }
+        )*
+    }
+}
+
+impl Schema {
+    pub fn message(name: &str) -> Schema {
+        Schema { name: name.to_string(), elements: vec![], num_children: 0, last: None, errors: vec![] }
+    }
+
+    primitive_fields! {
+        Type::BOOLEAN => required_boolean, optional_boolean, repeated_boolean;
+        Type::INT32 => required_int32, optional_int32, repeated_int32;
+        Type::INT64 => required_int64, optional_int64, repeated_int64;
+        Type::INT96 => required_int96, optional_int96, repeated_int96;
+        Type::FLOAT => required_float, optional_float, repeated_float;
+        Type::DOUBLE => required_double, optional_double, repeated_double;
+        Type::BYTE_ARRAY => required_binary, optional_binary, repeated_binary;
+    }
+
+    pub fn required_string(self, name: &str) -> Schema {
+        self.required_binary(name).logical(LogicalType::STRING(StringType::new()))
+    }
+
+    pub fn optional_string(self, name: &str) -> Schema {
+        self.optional_binary(name).logical(LogicalType::STRING(StringType::new()))
+    }
+
+    pub fn primitive(self, repetition: FieldRepetitionType, type_: Type, name: &str) -> Schema {
+        if type_ == Type::FIXED_LEN_BYTE_ARRAY {
+            return self.error(format!("Field '{}' needs a length, use fixed_len_byte_array", name))
+        }
+        self.push(SchemaElement::new(type_, None, repetition, name.to_string(), None, None, None, None, None, None), vec![])
+    }
+
+    pub fn fixed_len_byte_array(self, repetition: FieldRepetitionType, length: i32, name: &str) -> Schema {
+        if length <= 0 {
+            return self.error(format!("Field '{}' must have positive length but got {}", name, length))
+        }
+        let element = SchemaElement::new(Type::FIXED_LEN_BYTE_ARRAY, length, repetition, name.to_string(),
+            None, None, None, None, None, None);
+        self.push(element, vec![])
+    }
+
+    pub fn group<F: FnOnce(Schema) -> Schema>(self, repetition: FieldRepetitionType, name: &str, fields: F) -> Schema {
+        let group = fields(Schema::message(name));
+        if group.num_children == 0 {
+            return self.error(format!("Group '{}' must have at least one field", name))
+        }
+        let element = SchemaElement::new(None, None, repetition, name.to_string(), group.num_children,
+            None, None, None, None, None);
+        let mut res = self.push(element, group.elements);
+        res.errors.extend(group.errors.iter().map(|e| format!("{}: {}", name, e)));
+        res
+    }
+
+    pub fn required_group<F: FnOnce(Schema) -> Schema>(self, name: &str, fields: F) -> Schema {
+        self.group(FieldRepetitionType::REQUIRED, name, fields)
+    }
+
+    pub fn optional_group<F: FnOnce(Schema) -> Schema>(self, name: &str, fields: F) -> Schema {
+        self.group(FieldRepetitionType::OPTIONAL, name, fields)
+    }
+
+    pub fn repeated_group<F: FnOnce(Schema) -> Schema>(self, name: &str, fields: F) -> Schema {
+        self.group(FieldRepetitionType::REPEATED, name, fields)
+    }
+
+    /// Standard 3-level LIST: `<repetition> group <name> (LIST) { repeated group list { <element> } }`.
+    /// `element` must add a single field named `element`.
+    pub fn list<F: FnOnce(Schema) -> Schema>(self, repetition: FieldRepetitionType, name: &str, element: F) -> Schema {
+        let list = element(Schema::message("list"));
+        let valid = top_level_names(&list.elements) == ["element"];
+        let res = self.group(repetition, name, |g| g.repeated_group("list", |_| list)).
+            logical(LogicalType::LIST(ListType::new()));
+        if valid { res } else {
+            res.error(format!("List '{}' must have a single field named 'element'", name))
+        }
+    }
+
+    pub fn required_list<F: FnOnce(Schema) -> Schema>(self, name: &str, element: F) -> Schema {
+        self.list(FieldRepetitionType::REQUIRED, name, element)
+    }
+
+    pub fn optional_list<F: FnOnce(Schema) -> Schema>(self, name: &str, element: F) -> Schema {
+        self.list(FieldRepetitionType::OPTIONAL, name, element)
+    }
+
+    /// Standard MAP: `<repetition> group <name> (MAP) { repeated group key_value { <key> <value> } }`.
+    /// `key_value` must add a required field named `key` and optionally a field named `value`.
+    pub fn map<F: FnOnce(Schema) -> Schema>(self, repetition: FieldRepetitionType, name: &str, key_value: F) -> Schema {
+        let key_value = key_value(Schema::message("key_value"));
+        let names = top_level_names(&key_value.elements);
+        let required_key = key_value.elements.first().is_some_and(|k| k.repetition_type == Some(FieldRepetitionType::REQUIRED));
+        let valid = required_key && (names == ["key"] || names == ["key", "value"]);
+        let res = self.group(repetition, name, |g| g.repeated_group("key_value", |_| key_value)).
+            logical(LogicalType::MAP(MapType::new()));
+        if valid { res } else {
+            res.error(format!("Map '{}' must have a required field 'key' and optional field 'value'", name))
+        }
+    }
+
+    pub fn required_map<F: FnOnce(Schema) -> Schema>(self, name: &str, key_value: F) -> Schema {
+        self.map(FieldRepetitionType::REQUIRED, name, key_value)
+    }
+
+    pub fn optional_map<F: FnOnce(Schema) -> Schema>(self, name: &str, key_value: F) -> Schema {
+        self.map(FieldRepetitionType::OPTIONAL, name, key_value)
+    }
+
+    /// Annotate the last field with logical type and its equivalent converted type
+    pub fn logical(mut self, logical_type: LogicalType) -> Schema {
+        match self.last {
+            Some(last) => set_logical_type(&mut self.elements[last], logical_type),
+            None => return self.error(format!("No field to annotate with {:?}", logical_type)),
+        }
+        self
+    }
+
+    pub fn field_id(mut self, field_id: i32) -> Schema {
+        match self.last {
+            Some(last) => self.elements[last].field_id = Some(field_id),
+            None => return self.error(format!("No field to set id {}", field_id)),
+        }
+        self
+    }
+
+    /// Flattened schema elements with the message as root
+    pub fn build(self) -> Result<Vec<SchemaElement>, String> {
+        let mut schema = vec![SchemaElement::new(None, None, None, self.name, self.num_children, None, None, None, None, None)];
+        schema.extend(self.elements);
+
+        let mut errors = self.errors;
+        if let Err(problems) = validate_schema(&schema) {
+            errors.extend(problems);
+        }
+        if !errors.is_empty() {
+            return Err(errors.join("; "))
+        }
+        Ok(schema)
+    }
+
+    fn push(mut self, element: SchemaElement, children: Vec<SchemaElement>) -> Schema {
+        if top_level_names(&self.elements).contains(&element.name.as_str()) {
+            return self.error(format!("Duplicate field '{}'", element.name))
+        }
+        self.last = Some(self.elements.len());
+        self.num_children += 1;
+        self.elements.push(element);
+        self.elements.extend(children);
+        self
+    }
+
+    fn error(mut self, error: String) -> Schema {
+        self.errors.push(error);
+        self
+    }
+}
+
+/// Names of the fields of a group, given its flattened elements
+fn top_level_names(elements: &[SchemaElement]) -> Vec<&str> {
+    let mut names = vec![];
+    let mut pos = 0;
+    while pos < elements.len() {
+        names.push(elements[pos].name.as_str());
+        pos += subtree_len(elements, pos);
+    }
+    names
+}
+
+/// Number of elements in the subtree starting at `pos`, including itself
+fn subtree_len(elements: &[SchemaElement], pos: usize) -> usize {
+    let mut len = 1;
+    for _ in 0..elements[pos].num_children.unwrap_or(0) {
+        if pos + len >= elements.len() { break }
+        len += subtree_len(elements, pos + len);
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, repetition: Option<FieldRepetitionType>, children: i32) -> SchemaElement {
+        SchemaElement::new(None, None, repetition, name.to_string(), children, None, None, None, None, None)
+    }
+
+    fn leaf(name: &str, repetition: FieldRepetitionType, type_: Type) -> SchemaElement {
+        SchemaElement::new(type_, None, repetition, name.to_string(), None, None, None, None, None, None)
+    }
+
+    #[test]
+    fn nested_levels() {
+        let schema = vec![
+            group("schema", None, 3),
+            leaf("id", FieldRepetitionType::REQUIRED, Type::INT64),
+            group("tags", Some(FieldRepetitionType::OPTIONAL), 1),
+            group("list", Some(FieldRepetitionType::REPEATED), 1),
+            leaf("element", FieldRepetitionType::OPTIONAL, Type::BYTE_ARRAY),
+            leaf("ts", FieldRepetitionType::OPTIONAL, Type::INT64),
+        ];
+
+        let columns = leaf_columns(&schema).unwrap();
+        assert_eq!(3, columns.len());
+
+        assert_eq!("id", columns[0].path_string());
+        assert_eq!((0, 0), (columns[0].max_def_level, columns[0].max_rep_level));
+
+        assert_eq!("tags.list.element", columns[1].path_string());
+        assert_eq!((3, 1), (columns[1].max_def_level, columns[1].max_rep_level));
+        assert_eq!(4, columns[1].schema_idx);
+
+        assert_eq!(Some(2), find_column(&columns, "ts"));
+        assert_eq!((1, 0), (columns[2].max_def_level, columns[2].max_rep_level));
+    }
+
+    #[test]
+    fn print() {
+        let mut id = leaf("id", FieldRepetitionType::REQUIRED, Type::INT64);
+        id.field_id = Some(1);
+        let mut tags = group("tags", Some(FieldRepetitionType::OPTIONAL), 1);
+        tags.logical_type = Some(LogicalType::LIST(ListType::new()));
+        let mut element = leaf("element", FieldRepetitionType::OPTIONAL, Type::BYTE_ARRAY);
+        element.converted_type = Some(ConvertedType::UTF8);
+        let mut ts = leaf("ts", FieldRepetitionType::OPTIONAL, Type::INT64);
+        ts.logical_type = Some(LogicalType::TIMESTAMP(TimestampType::new(true, TimeUnit::MICROS(MicroSeconds::new()))));
+        let mut price = leaf("price", FieldRepetitionType::REQUIRED, Type::FIXED_LEN_BYTE_ARRAY);
+        price.type_length = Some(8);
+        price.converted_type = Some(ConvertedType::DECIMAL);
+        price.precision = Some(18);
+        price.scale = Some(2);
+
+        let schema = vec![
+            group("schema", None, 4),
+            id,
+            tags,
+            group("list", Some(FieldRepetitionType::REPEATED), 1),
+            element,
+            ts,
+            price,
+        ];
+
+        assert_eq!("\
+message schema {
+  required int64 id = 1;
+  optional group tags (LIST) {
+    repeated group list {
+      optional binary element (UTF8);
+    }
+  }
+  optional int64 ts (TIMESTAMP(MICROS,true));
+  required fixed_len_byte_array(8) price (DECIMAL(18,2));
+}
+", print_schema(&schema).unwrap());
+
+        assert!(print_schema(&schema[..4]).is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let schema = parse_schema("
+            message m {
+              required int64 id = 7;
+              optional binary name (STRING);
+              optional group tags (LIST) {
+                repeated group list {
+                  optional fixed_len_byte_array(16) element (UUID);
+                }
+              }
+              optional int64 ts (TIMESTAMP(NANOS,false));
+              required int32 price (DECIMAL(9,2));
+              optional int32 legacy (UINT_8);
+            }").unwrap();
+
+        assert_eq!(9, schema.len());
+        assert_eq!(("m", Some(6)), (schema[0].name.as_str(), schema[0].num_children));
+        assert_eq!(Some(7), schema[1].field_id);
+        assert_eq!(Some(ConvertedType::UTF8), schema[2].converted_type);
+        assert_eq!(Some(LogicalType::STRING(StringType::new())), schema[2].logical_type);
+        assert_eq!((None, Some(1)), (schema[3].type_, schema[3].num_children));
+        assert_eq!(Some(FieldRepetitionType::REPEATED), schema[4].repetition_type);
+        assert_eq!(Some(16), schema[5].type_length);
+        assert_eq!(None, schema[6].converted_type);
+        assert_eq!((Some(9), Some(2)), (schema[7].precision, schema[7].scale));
+        assert_eq!((None, Some(ConvertedType::UINT_8)), (schema[8].logical_type.clone(), schema[8].converted_type));
+
+        assert_eq!(3, leaf_columns(&schema).unwrap().iter().filter(|c| c.max_def_level == 1).count());
+    }
+
+    #[test]
+    fn print_parse_roundtrip() {
+        let text = "\
+message schema {
+  required int64 id = 1;
+  optional group m (MAP) {
+    repeated group key_value {
+      required binary key (UTF8);
+      optional int32 value (INTEGER(16,false));
+    }
+  }
+  optional int64 t (TIME(MICROS,true));
+  required fixed_len_byte_array(2) f (FLOAT16);
+  optional int96 ts;
+}
+";
+        assert_eq!(text, print_schema(&parse_schema(text).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_schema("message m { required int64 id }").is_err());
+        assert!(parse_schema("message m { required text id; }").is_err());
+        assert!(parse_schema("message m { optional int32 d (DECIMAL(9)); }").is_err());
+        assert!(parse_schema("message m { required int32 a (INTEGER(12,true)); }").is_err());
+        assert!(parse_schema("message m { } }").is_err());
+        assert!(parse_schema("message m { required group g {").is_err());
+    }
+
+    #[test]
+    fn builder() {
+        let schema = Schema::message("m").
+            required_int64("id").field_id(1).
+            optional_string("name").
+            optional_group("addr", |g| g.required_binary("street").optional_int32("zip").logical(LogicalType::INTEGER(IntType::new(32, false)))).
+            optional_list("tags", |e| e.optional_string("element")).
+            required_map("attrs", |kv| kv.required_string("key").optional_double("value")).
+            fixed_len_byte_array(FieldRepetitionType::OPTIONAL, 16, "u").logical(LogicalType::UUID(UUIDType::new())).
+            optional_int32("price").logical(LogicalType::DECIMAL(DecimalType::new(2, 9))).
+            build().unwrap();
+
+        assert_eq!("\
+message m {
+  required int64 id = 1;
+  optional binary name (STRING);
+  optional group addr {
+    required binary street;
+    optional int32 zip (INTEGER(32,false));
+  }
+  optional group tags (LIST) {
+    repeated group list {
+      optional binary element (STRING);
+    }
+  }
+  required group attrs (MAP) {
+    repeated group key_value {
+      required binary key (STRING);
+      optional double value;
+    }
+  }
+  optional fixed_len_byte_array(16) u (UUID);
+  optional int32 price (DECIMAL(9,2));
+}
+", print_schema(&schema).unwrap());
+        assert_eq!(Some(ConvertedType::UINT_32), schema[5].converted_type);
+        assert_eq!((Some(9), Some(2)), (schema[14].precision, schema[14].scale));
+        assert_eq!(schema, parse_schema(&print_schema(&schema).unwrap()).unwrap());
+    }
+
+    #[test]
+    fn builder_errors() {
+        let err = |schema: Schema| schema.build().unwrap_err();
+
+        assert!(err(Schema::message("m").required_int64("a").optional_int32("a")).contains("Duplicate field 'a'"));
+        assert!(err(Schema::message("m").optional_group("g", |g| g)).contains("at least one field"));
+        assert!(err(Schema::message("m").optional_list("l", |e| e.optional_int32("item"))).contains("'element'"));
+        assert!(err(Schema::message("m").optional_map("m", |kv| kv.optional_int32("key"))).contains("'key'"));
+        assert!(err(Schema::message("m").required_int64("s").logical(LogicalType::STRING(StringType::new()))).
+            contains("not allowed"));
+        assert!(err(Schema::message("m").optional_group("g", |g| g.field_id(3).required_int32("a"))).contains("g: No field"));
+        assert!(err(Schema::message("m")).contains("at least one field"));
+    }
+
+    #[test]
+    fn valid_schemas() {
+        let schema = parse_schema("
+            message m {
+              required int64 id;
+              optional group tags (LIST) {
+                repeated binary tag (UTF8);
+              }
+              optional group attrs (MAP) {
+                repeated group map {
+                  required binary key (STRING);
+                }
+              }
+              optional group legacy (MAP) {
+                repeated group map (MAP_KEY_VALUE) {
+                  required binary key (UTF8);
+                  optional int32 value;
+                }
+              }
+              optional group misplaced (MAP_KEY_VALUE) {
+                repeated group map {
+                  required binary key (UTF8);
+                }
+              }
+              required fixed_len_byte_array(16) price (DECIMAL(38,2));
+            }").unwrap();
+        assert_eq!(Ok(()), validate_schema(&schema));
+    }
+
+    #[test]
+    fn invalid_schemas() {
+        let problems = |text: &str| validate_schema(&parse_schema(text).unwrap()).unwrap_err();
+
+        assert_eq!(vec!["price: decimal precision 10 does not fit INT32 (at most 9)"],
+            problems("message m { required int32 price (DECIMAL(10,2)); }"));
+        assert_eq!(vec!["d: decimal precision 10 does not fit FIXED_LEN_BYTE_ARRAY (at most 9)"],
+            problems("message m { required fixed_len_byte_array(4) d (DECIMAL(10,2)); }"));
+        assert_eq!(vec!["a.l: LIST must have a single repeated field"],
+            problems("message m { required group a { optional group l (LIST) { optional int32 e; } } }"));
+        assert_eq!(vec!["m: MAP key must be required"],
+            problems("message m { optional group m (MAP) { repeated group kv { optional int32 key; } } }"));
+        assert_eq!(vec!["m: MAP must have a single repeated field"],
+            problems("message m { optional group m (MAP_KEY_VALUE) { required int32 key; } }"));
+        assert_eq!(vec!["a: duplicate field name", "b: FIXED_LEN_BYTE_ARRAY needs positive type_length but has Some(0)"],
+            problems("message m { required int32 a; optional int64 a; required fixed_len_byte_array(0) b; }"));
+        assert!(problems("message m { required int32 s (STRING); }")[0].starts_with("s: Logical type String"));
+
+        let mut schema = parse_schema("message m { required group g { required int32 a; } }").unwrap();
+        schema[1].num_children = Some(2);
+        assert_eq!(vec!["g: declares 2 children but schema ends after 1"], validate_schema(&schema).unwrap_err());
+        schema[1].num_children = Some(0);
+        assert_eq!(vec!["g: group must have at least one field", "m: 1 elements after the last field of the root"],
+            validate_schema(&schema).unwrap_err());
+        schema[0].type_ = Some(Type::INT32);
+        assert_eq!("m: root must be a group but has type Some(INT32)", validate_schema(&schema).unwrap_err()[0]);
+    }
+}
@@ -0,0 +1,277 @@
+use parquet::*;
+use value::Value;
+use logical::{Logical, resolve};
+
+/// Unit of TIME and TIMESTAMP values
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Millis,
+    Micros,
+    /// NANOS unit of newer writers, also used for legacy INT96 timestamps
+    Nanos,
+}
+
+impl Unit {
+    pub fn per_second(&self) -> i64 {
+        match *self {
+            Unit::Millis => 1_000,
+            Unit::Micros => 1_000_000,
+            Unit::Nanos => 1_000_000_000,
+        }
+    }
+}
+
+/// Temporal logical type of a column
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TemporalType {
+    Date,
+    Time { unit: Unit, adjusted_to_utc: bool },
+    Timestamp { unit: Unit, adjusted_to_utc: bool },
+}
+
+/// Decoded temporal value
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Temporal {
+    /// Days since Unix epoch
+    Date(i32),
+    /// Time since midnight
+    Time { value: i64, unit: Unit, adjusted_to_utc: bool },
+    /// Time since Unix epoch
+    Timestamp { value: i64, unit: Unit, adjusted_to_utc: bool },
+}
+
+/// Julian day of Unix epoch
+const JULIAN_DAY_OF_EPOCH: i64 = 2_440_588;
+const NANOS_PER_DAY: i64 = 86_400 * 1_000_000_000;
+
+/// Temporal type of a column from its resolved logical type, fails when the annotation is invalid.
+/// Unannotated INT96 is a nanosecond timestamp, adjusted to UTC as Spark and Hive write it.
+pub fn temporal_type(element: &SchemaElement) -> Result<Option<TemporalType>, String> {
+    let logical = resolve(element)?;
+    if element.type_ == Some(Type::INT96) && logical.is_none() {
+        return Ok(Some(TemporalType::Timestamp { unit: Unit::Nanos, adjusted_to_utc: true }))
+    }
+    Ok(logical.and_then(TemporalType::from_logical))
+}
+
+impl TemporalType {
+    /// Temporal type of a DATE, TIME or TIMESTAMP logical type
+    pub fn from_logical(logical: Logical) -> Option<TemporalType> {
+        match logical {
+            Logical::Date => Some(TemporalType::Date),
+            Logical::Time { unit, adjusted_to_utc } => Some(TemporalType::Time { unit, adjusted_to_utc }),
+            Logical::Timestamp { unit, adjusted_to_utc } => Some(TemporalType::Timestamp { unit, adjusted_to_utc }),
+            _ => None,
+        }
+    }
+
+    /// Decode physical value: INT32 for DATE and TIME(MILLIS), INT96 for legacy timestamps, INT64 otherwise
+    pub fn decode(&self, value: &Value) -> Result<Temporal, String> {
+        match (*self, value) {
+            (TemporalType::Timestamp { unit: Unit::Nanos, adjusted_to_utc }, Value::Int96(v)) => {
+                let value = int96_to_nanos(v)?;
+                Ok(Temporal::Timestamp { value, unit: Unit::Nanos, adjusted_to_utc })
+            },
+            (TemporalType::Date, &Value::Int32(days)) => Ok(Temporal::Date(days)),
+            (TemporalType::Time { unit: Unit::Millis, adjusted_to_utc }, &Value::Int32(v)) =>
+                Ok(Temporal::Time { value: v as i64, unit: Unit::Millis, adjusted_to_utc }),
+            (TemporalType::Time { unit, adjusted_to_utc }, &Value::Int64(v)) if unit != Unit::Millis =>
+                Ok(Temporal::Time { value: v, unit, adjusted_to_utc }),
+            (TemporalType::Timestamp { unit, adjusted_to_utc }, &Value::Int64(v)) =>
+                Ok(Temporal::Timestamp { value: v, unit, adjusted_to_utc }),
+            _ => Err(format!("Value {:?} can not be decoded as {:?}", value, self)),
+        }
+    }
+
+    /// Encode as physical value of a column of `type_`, inverse of `decode`.
+    /// Units must match, except that INT96 columns take timestamps of any unit.
+    pub fn encode(&self, temporal: &Temporal, type_: Type) -> Result<Value, String> {
+        match (*self, *temporal, type_) {
+            (TemporalType::Timestamp { .. }, Temporal::Timestamp { .. }, Type::INT96) => temporal.to_int96(),
+            (TemporalType::Date, Temporal::Date(days), Type::INT32) => Ok(Value::Int32(days)),
+            (TemporalType::Time { unit: Unit::Millis, .. }, Temporal::Time { value, unit: Unit::Millis, .. }, Type::INT32)
+                if value >= 0 && value <= i32::MAX as i64 => Ok(Value::Int32(value as i32)),
+            (TemporalType::Time { unit, .. }, Temporal::Time { value, unit: value_unit, .. }, Type::INT64)
+                if unit == value_unit => Ok(Value::Int64(value)),
+            (TemporalType::Timestamp { unit, .. }, Temporal::Timestamp { value, unit: value_unit, .. }, Type::INT64)
+                if unit == value_unit => Ok(Value::Int64(value)),
+            _ => Err(format!("{:?} can not be encoded as {:?} of {:?}", temporal, self, type_)),
+        }
+    }
+}
+
+/// Convert INT96 timestamp (nanoseconds of day followed by Julian day) into nanoseconds since Unix epoch.
+/// Error if nanoseconds exceed a day, or it does not fit i64, i.e. is outside of years 1677..2262.
+pub fn int96_to_nanos(v: &[u32; 3]) -> Result<i64, String> {
+    let nanos_of_day = (v[1] as u64) << 32 | v[0] as u64;
+    if nanos_of_day >= NANOS_PER_DAY as u64 {
+        return Err(format!("INT96 timestamp has {} nanoseconds of day: {:?}", nanos_of_day, v))
+    }
+    let days = v[2] as i64 - JULIAN_DAY_OF_EPOCH;
+    days.checked_mul(NANOS_PER_DAY).and_then(|nanos| nanos.checked_add(nanos_of_day as i64)).
+        ok_or_else(|| format!("INT96 timestamp out of range: {:?}", v))
+}
+
+/// Convert nanoseconds since Unix epoch into INT96 timestamp
+pub fn nanos_to_int96(nanos: i64) -> [u32; 3] {
+    let mut days = nanos / NANOS_PER_DAY;
+    let mut nanos_of_day = nanos % NANOS_PER_DAY;
+    if nanos_of_day < 0 {
+        days -= 1;
+        nanos_of_day += NANOS_PER_DAY;
+    }
+    [nanos_of_day as u32, (nanos_of_day >> 32) as u32, (days + JULIAN_DAY_OF_EPOCH) as u32]
+}
+
+impl Temporal {
+    /// Encode timestamp as INT96 value, for compatibility with old Hive and Impala readers
+    pub fn to_int96(&self) -> Result<Value, String> {
+        match *self {
+            Temporal::Timestamp { value, unit, .. } => {
+                let nanos = value.checked_mul(1_000_000_000 / unit.per_second()).
+                    ok_or_else(|| format!("Timestamp {} {:?} does not fit INT96 nanoseconds", value, unit))?;
+                Ok(Value::Int96(nanos_to_int96(nanos)))
+            },
+            _ => Err(format!("Only timestamps can be written as INT96 but got {:?}", self)),
+        }
+    }
+
+    /// Seconds and sub-second nanoseconds, rounded towards negative infinity
+    #[cfg_attr(not(feature = "chrono"), allow(dead_code))]
+    fn split(value: i64, unit: Unit) -> (i64, u32) {
+        let per_second = unit.per_second();
+        let mut secs = value / per_second;
+        let mut frac = value % per_second;
+        if frac < 0 {
+            secs -= 1;
+            frac += per_second;
+        }
+        (secs, (frac * (1_000_000_000 / per_second)) as u32)
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn to_naive_date(&self) -> Option<::chrono::NaiveDate> {
+        match *self {
+            // 719163 days from 0001-01-01 to 1970-01-01
+            Temporal::Date(days) => ::chrono::NaiveDate::from_num_days_from_ce_opt(days.checked_add(719_163)?),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn to_naive_time(&self) -> Option<::chrono::NaiveTime> {
+        match *self {
+            Temporal::Time { value, unit, .. } => {
+                let (secs, nanos) = Temporal::split(value, unit);
+                if !(0..86_400).contains(&secs) { return None }
+                ::chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs as u32, nanos)
+            },
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    pub fn to_naive_datetime(&self) -> Option<::chrono::NaiveDateTime> {
+        match *self {
+            Temporal::Timestamp { value, unit, .. } => {
+                let (secs, nanos) = Temporal::split(value, unit);
+                ::chrono::DateTime::from_timestamp(secs, nanos).map(|t| t.naive_utc())
+            },
+            _ => None,
+        }
+    }
+
+    /// Instant in UTC, None if the timestamp is local (not adjusted to UTC)
+    #[cfg(feature = "chrono")]
+    pub fn to_utc_datetime(&self) -> Option<::chrono::DateTime<::chrono::Utc>> {
+        use chrono::TimeZone;
+        match *self {
+            Temporal::Timestamp { adjusted_to_utc: true, .. } =>
+                self.to_naive_datetime().map(|dt| ::chrono::Utc.from_utc_datetime(&dt)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn element(type_: Type, converted_type: Option<ConvertedType>, logical_type: Option<LogicalType>) -> SchemaElement {
+        SchemaElement::new(type_, None, FieldRepetitionType::REQUIRED, "t".to_string(), None,
+            converted_type, None, None, None, logical_type)
+    }
+
+    #[test]
+    fn resolve_types() {
+        let local_ts = element(Type::INT64, None,
+            Some(LogicalType::TIMESTAMP(TimestampType::new(false, TimeUnit::MICROS(MicroSeconds::new())))));
+        assert_eq!(Ok(Some(TemporalType::Timestamp { unit: Unit::Micros, adjusted_to_utc: false })), temporal_type(&local_ts));
+
+        let legacy = element(Type::INT32, Some(ConvertedType::TIME_MILLIS), None);
+        assert_eq!(Ok(Some(TemporalType::Time { unit: Unit::Millis, adjusted_to_utc: true })), temporal_type(&legacy));
+
+        assert_eq!(Ok(None), temporal_type(&element(Type::INT64, None, None)));
+        assert_eq!(Ok(Some(TemporalType::Timestamp { unit: Unit::Nanos, adjusted_to_utc: true })),
+            temporal_type(&element(Type::INT96, None, None)));
+        // TIME(MILLIS) is stored as INT32
+        assert!(temporal_type(&element(Type::INT64, Some(ConvertedType::TIME_MILLIS), None)).is_err());
+    }
+
+    #[test]
+    fn int96_timestamps() {
+        // 2000-01-01 00:00:00.000000001, as written by Spark
+        let v = [1, 0, 2_451_545];
+        assert_eq!(Ok(946_684_800_000_000_001), int96_to_nanos(&v));
+        // corrupt nanoseconds of day, one past the day and the largest value, and years past 2262
+        assert!(int96_to_nanos(&[NANOS_PER_DAY as u32, (NANOS_PER_DAY >> 32) as u32, 2_451_545]).is_err());
+        assert!(int96_to_nanos(&[u32::MAX, u32::MAX, 2_451_545]).is_err());
+        assert!(int96_to_nanos(&[0, 0, 3_000_000]).is_err());
+        assert_eq!(v, nanos_to_int96(946_684_800_000_000_001));
+        assert_eq!([(NANOS_PER_DAY - 1) as u32, ((NANOS_PER_DAY - 1) >> 32) as u32, 2_440_587], nanos_to_int96(-1));
+
+        let ts = TemporalType::Timestamp { unit: Unit::Nanos, adjusted_to_utc: true }.decode(&Value::Int96(v)).unwrap();
+        assert_eq!(Temporal::Timestamp { value: 946_684_800_000_000_001, unit: Unit::Nanos, adjusted_to_utc: true }, ts);
+
+        let millis = Temporal::Timestamp { value: 946_684_800_000, unit: Unit::Millis, adjusted_to_utc: true };
+        assert_eq!(Value::Int96([0, 0, 2_451_545]), millis.to_int96().unwrap());
+        assert!(Temporal::Date(1).to_int96().is_err());
+    }
+
+    #[test]
+    fn decode_values() {
+        let ts = TemporalType::Timestamp { unit: Unit::Millis, adjusted_to_utc: true };
+        assert_eq!(Temporal::Timestamp { value: -1, unit: Unit::Millis, adjusted_to_utc: true }, ts.decode(&Value::Int64(-1)).unwrap());
+        assert!(ts.decode(&Value::Int32(1)).is_err());
+        assert!(TemporalType::Time { unit: Unit::Micros, adjusted_to_utc: true }.decode(&Value::Int32(1)).is_err());
+        assert_eq!((-1, 999_000_000), Temporal::split(-1, Unit::Millis));
+    }
+
+    #[test]
+    fn encode_values() {
+        let ts = TemporalType::Timestamp { unit: Unit::Millis, adjusted_to_utc: true };
+        let value = Temporal::Timestamp { value: -1, unit: Unit::Millis, adjusted_to_utc: true };
+        assert_eq!(Value::Int64(-1), ts.encode(&value, Type::INT64).unwrap());
+        assert_eq!(Value::Int96(nanos_to_int96(-1_000_000)), ts.encode(&value, Type::INT96).unwrap());
+        let micros = Temporal::Timestamp { value: -1, unit: Unit::Micros, adjusted_to_utc: true };
+        assert!(ts.encode(&micros, Type::INT64).is_err());
+
+        let time = TemporalType::Time { unit: Unit::Millis, adjusted_to_utc: true };
+        let value = Temporal::Time { value: 1_000, unit: Unit::Millis, adjusted_to_utc: true };
+        assert_eq!(value, time.decode(&time.encode(&value, Type::INT32).unwrap()).unwrap());
+        assert_eq!(Value::Int32(7), TemporalType::Date.encode(&Temporal::Date(7), Type::INT32).unwrap());
+        assert!(TemporalType::Date.encode(&value, Type::INT32).is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_conversion() {
+        assert_eq!("1970-01-02", Temporal::Date(1).to_naive_date().unwrap().to_string());
+        let time = Temporal::Time { value: 3_661_000_001, unit: Unit::Micros, adjusted_to_utc: true };
+        assert_eq!("01:01:01.000001", time.to_naive_time().unwrap().to_string());
+        let ts = Temporal::Timestamp { value: 1_500, unit: Unit::Millis, adjusted_to_utc: true };
+        assert_eq!("1970-01-01 00:00:01.500 UTC", ts.to_utc_datetime().unwrap().to_string());
+        let local = Temporal::Timestamp { value: 1_500, unit: Unit::Millis, adjusted_to_utc: false };
+        assert!(local.to_utc_datetime().is_none());
+    }
+}
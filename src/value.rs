@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use byteorder::{ByteOrder, LittleEndian};
+use parquet::*;
+use logical::{Logical, resolve};
+
+/// Physical value, as stored in PLAIN encoding.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Boolean(bool),
+    Int32(i32),
+    Int64(i64),
+    Int96([u32; 3]),
+    Float(f32),
+    Double(f64),
+    Bytes(Vec<u8>),
+}
+
+/// How values of a column compare to each other, defined by physical and logical type.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Signed,
+    Unsigned,
+    Undefined,
+}
+
+impl Value {
+    /// Decode value in statistics/page index format: PLAIN, but byte arrays without length prefix.
+    pub fn from_plain(type_: Type, data: &[u8]) -> Result<Value, String> {
+        let expect_len = |len: usize| {
+            if data.len() == len { Ok(()) } else {
+                Err(format!("Expected {} bytes for {:?} value but got {}", len, type_, data.len()))
+            }
+        };
+
+        match type_ {
+            Type::BOOLEAN => { expect_len(1)?; Ok(Value::Boolean(data[0] != 0)) },
+            Type::INT32 => { expect_len(4)?; Ok(Value::Int32(LittleEndian::read_i32(data))) },
+            Type::INT64 => { expect_len(8)?; Ok(Value::Int64(LittleEndian::read_i64(data))) },
+            Type::INT96 => {
+                expect_len(12)?;
+                Ok(Value::Int96([
+                    LittleEndian::read_u32(&data[0..]),
+                    LittleEndian::read_u32(&data[4..]),
+                    LittleEndian::read_u32(&data[8..]),
+                ]))
+            },
+            Type::FLOAT => { expect_len(4)?; Ok(Value::Float(LittleEndian::read_f32(data))) },
+            Type::DOUBLE => { expect_len(8)?; Ok(Value::Double(LittleEndian::read_f64(data))) },
+            Type::BYTE_ARRAY | Type::FIXED_LEN_BYTE_ARRAY => Ok(Value::Bytes(data.to_vec())),
+        }
+    }
+
+    /// Encode value in statistics/page index format, inverse of `from_plain`
+    pub fn to_plain(&self) -> Vec<u8> {
+        match *self {
+            Value::Boolean(v) => vec![v as u8],
+            Value::Int32(v) => { let mut buf = vec![0; 4]; LittleEndian::write_i32(&mut buf, v); buf },
+            Value::Int64(v) => { let mut buf = vec![0; 8]; LittleEndian::write_i64(&mut buf, v); buf },
+            Value::Int96(v) => {
+                let mut buf = vec![0; 12];
+                LittleEndian::write_u32(&mut buf[0..], v[0]);
+                LittleEndian::write_u32(&mut buf[4..], v[1]);
+                LittleEndian::write_u32(&mut buf[8..], v[2]);
+                buf
+            },
+            Value::Float(v) => { let mut buf = vec![0; 4]; LittleEndian::write_f32(&mut buf, v); buf },
+            Value::Double(v) => { let mut buf = vec![0; 8]; LittleEndian::write_f64(&mut buf, v); buf },
+            Value::Bytes(ref v) => v.clone(),
+        }
+    }
+
+    /// Whether the value can be stored in a column of physical type `type_` with `type_length`
+    pub fn matches(&self, type_: Type, type_length: i32) -> bool {
+        match (type_, self) {
+            (Type::BOOLEAN, Value::Boolean(_)) | (Type::INT32, Value::Int32(_)) | (Type::INT64, Value::Int64(_)) |
+            (Type::INT96, Value::Int96(_)) | (Type::FLOAT, Value::Float(_)) | (Type::DOUBLE, Value::Double(_)) |
+            (Type::BYTE_ARRAY, &Value::Bytes(_)) => true,
+            (Type::FIXED_LEN_BYTE_ARRAY, Value::Bytes(bytes)) => bytes.len() as i32 == type_length,
+            _ => false,
+        }
+    }
+
+    /// Size of the value in a PLAIN encoded page, a boolean counted as a byte
+    pub fn plain_size(&self) -> usize {
+        match *self {
+            Value::Boolean(_) => 1,
+            Value::Int32(_) | Value::Float(_) => 4,
+            Value::Int64(_) | Value::Double(_) => 8,
+            Value::Int96(_) => 12,
+            Value::Bytes(ref v) => 4 + v.len(),
+        }
+    }
+
+    /// Compare two values of the same physical type.
+    /// Returns None if values are of different types, order is undefined or a float is NaN.
+    pub fn compare(&self, other: &Value, order: SortOrder) -> Option<Ordering> {
+        match (order, self, other) {
+            (SortOrder::Undefined, _, _) => None,
+            (_, &Value::Boolean(a), &Value::Boolean(b)) => Some(a.cmp(&b)),
+            (SortOrder::Signed, &Value::Int32(a), &Value::Int32(b)) => Some(a.cmp(&b)),
+            (SortOrder::Unsigned, &Value::Int32(a), &Value::Int32(b)) => Some((a as u32).cmp(&(b as u32))),
+            (SortOrder::Signed, &Value::Int64(a), &Value::Int64(b)) => Some(a.cmp(&b)),
+            (SortOrder::Unsigned, &Value::Int64(a), &Value::Int64(b)) => Some((a as u64).cmp(&(b as u64))),
+            (_, &Value::Float(a), &Value::Float(b)) => a.partial_cmp(&b),
+            (_, &Value::Double(a), &Value::Double(b)) => a.partial_cmp(&b),
+            (SortOrder::Unsigned, Value::Bytes(a), Value::Bytes(b)) => Some(a.cmp(b)),
+            (SortOrder::Signed, Value::Bytes(a), Value::Bytes(b)) => Some(compare_signed_bytes(a, b)),
+            _ => None,
+        }
+    }
+}
+
+/// Sort order of a leaf column as defined by the spec for `TypeDefinedOrder`,
+/// undefined when its logical type annotation is invalid.
+pub fn sort_order(element: &SchemaElement) -> SortOrder {
+    let logical = match resolve(element) {
+        Ok(logical) => logical,
+        Err(_) => return SortOrder::Undefined,
+    };
+
+    match (element.type_, logical) {
+        (_, Some(Logical::Float16)) => SortOrder::Signed,
+        (Some(Type::BOOLEAN), _) => SortOrder::Unsigned,
+        (Some(Type::INT32), Some(Logical::Integer { signed: false, .. })) |
+        (Some(Type::INT64), Some(Logical::Integer { signed: false, .. })) => SortOrder::Unsigned,
+        (Some(Type::INT32), _) | (Some(Type::INT64), _) => SortOrder::Signed,
+        (Some(Type::FLOAT), _) | (Some(Type::DOUBLE), _) => SortOrder::Signed,
+        (Some(Type::BYTE_ARRAY), Some(Logical::Decimal { .. })) |
+        (Some(Type::FIXED_LEN_BYTE_ARRAY), Some(Logical::Decimal { .. })) => SortOrder::Signed,
+        (Some(Type::FIXED_LEN_BYTE_ARRAY), Some(Logical::Interval)) => SortOrder::Undefined,
+        (Some(Type::BYTE_ARRAY), _) | (Some(Type::FIXED_LEN_BYTE_ARRAY), _) => SortOrder::Unsigned,
+        (Some(Type::INT96), _) | (None, _) => SortOrder::Undefined,
+    }
+}
+
+/// Whether the column holds FLOAT16 values, 2 byte FIXED_LEN_BYTE_ARRAYs
+pub fn is_float16(element: &SchemaElement) -> bool {
+    resolve(element) == Ok(Some(Logical::Float16))
+}
+
+/// Value in the form its column order compares: FLOAT16 bytes become `Value::Float`,
+/// other values are returned as is.
+pub fn comparable(element: &SchemaElement, value: Value) -> Result<Value, String> {
+    match value {
+        Value::Bytes(ref bytes) if is_float16(element) => {
+            if bytes.len() != 2 {
+                return Err(format!("Expected 2 bytes for FLOAT16 value but got {}", bytes.len()))
+            }
+            Ok(Value::Float(float16_to_f32(LittleEndian::read_u16(bytes))))
+        },
+        value => Ok(value),
+    }
+}
+
+/// Narrow `f32` to IEEE 754 half-precision bits, rounding to nearest even.
+/// Values out of half-precision range become infinity, NaN stays NaN.
+pub fn f32_to_float16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exp == 0xff {
+        return sign | 0x7c00 | if mantissa != 0 { 0x200 } else { 0 }
+    }
+    let exp = exp - 127 + 15;
+    if exp >= 0x1f {
+        return sign | 0x7c00
+    }
+
+    // subnormal halves have exponent 0 and count units of 2^-24
+    let (mantissa, shift) = if exp <= 0 { (mantissa | 0x80_0000, (14 - exp) as u32) } else { (mantissa, 13) };
+    if shift > 24 {
+        return sign
+    }
+    let mut half = (mantissa >> shift) as u16;
+    if exp > 0 {
+        half |= (exp as u16) << 10;
+    }
+    let rest = mantissa & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    // carry of the mantissa into the exponent is the correct rounding
+    if rest > halfway || (rest == halfway && half & 1 == 1) {
+        half += 1;
+    }
+    sign | half
+}
+
+/// Widen IEEE 754 half-precision bits to `f32`, exactly
+pub fn float16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits & 0x8000) as u32) << 16;
+    let exp = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+
+    let bits = match exp {
+        0 if mantissa == 0 => sign,
+        // subnormal: value is mantissa * 2^-24
+        0 => {
+            let value = mantissa as f32 / (1 << 24) as f32;
+            return if sign != 0 { -value } else { value }
+        },
+        0x1f => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exp + 127 - 15) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// Compare big-endian two's complement integers of possibly different length.
+fn compare_signed_bytes(a: &[u8], b: &[u8]) -> Ordering {
+    let negative = |v: &[u8]| v.first().is_some_and(|&b| b & 0x80 != 0);
+    match (negative(a), negative(b)) {
+        (true, false) => return Ordering::Less,
+        (false, true) => return Ordering::Greater,
+        _ => {}
+    }
+
+    // same sign: sign-extend shorter value and compare bytes as unsigned
+    let ext = if negative(a) { 0xff_u8 } else { 0_u8 };
+    let len = a.len().max(b.len());
+    let byte_at = |v: &[u8], i: usize| if i < len - v.len() { ext } else { v[i - (len - v.len())] };
+    (0..len).map(|i| byte_at(a, i).cmp(&byte_at(b, i))).
+        find(|&o| o != Ordering::Equal).
+        unwrap_or(Ordering::Equal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_roundtrip() {
+        let v = Value::Int64(-42);
+        assert_eq!(v, Value::from_plain(Type::INT64, &v.to_plain()).unwrap());
+        assert!(Value::from_plain(Type::INT32, &[1, 2]).is_err());
+        assert!(v.matches(Type::INT64, 0));
+        assert!(!v.matches(Type::INT32, 0));
+        assert!(Value::Bytes(vec![1, 2]).matches(Type::FIXED_LEN_BYTE_ARRAY, 2));
+        assert!(!Value::Bytes(vec![1, 2]).matches(Type::FIXED_LEN_BYTE_ARRAY, 3));
+    }
+
+    #[test]
+    fn ordering() {
+        let (a, b) = (Value::Int32(-1), Value::Int32(1));
+        assert_eq!(Some(Ordering::Less), a.compare(&b, SortOrder::Signed));
+        assert_eq!(Some(Ordering::Greater), a.compare(&b, SortOrder::Unsigned));
+
+        let (a, b) = (Value::Bytes(vec![0xff, 0x00]), Value::Bytes(vec![0x01]));
+        assert_eq!(Some(Ordering::Less), a.compare(&b, SortOrder::Signed));
+        assert_eq!(Some(Ordering::Greater), a.compare(&b, SortOrder::Unsigned));
+        assert_eq!(Ordering::Equal, compare_signed_bytes(&[0xff, 0xfe], &[0xfe]));
+    }
+
+    #[test]
+    fn float16() {
+        assert_eq!(1.0, float16_to_f32(0x3c00));
+        assert_eq!(-2.5, float16_to_f32(0xc100));
+        assert_eq!(65504.0, float16_to_f32(0x7bff));
+        assert_eq!(2f32.powi(-24), float16_to_f32(0x0001));
+        assert_eq!(f32::INFINITY, float16_to_f32(0x7c00));
+        assert!(float16_to_f32(0x7e00).is_nan());
+        for &bits in &[0x3c00, 0xc100, 0x7bff, 0x0001, 0x03ff, 0x8000, 0x7c00] {
+            assert_eq!(bits, f32_to_float16(float16_to_f32(bits)));
+        }
+        assert_eq!(0x7c00, f32_to_float16(65520.0));
+        assert_eq!(0x3c00, f32_to_float16(1.0 + 2f32.powi(-11)));
+        assert_eq!(0x3c02, f32_to_float16(1.0 + 3.0 * 2f32.powi(-11)));
+        assert_eq!(0x0000, f32_to_float16(2f32.powi(-26)));
+        assert!(float16_to_f32(f32_to_float16(f32::NAN)).is_nan());
+
+        let element = SchemaElement::new(Type::FIXED_LEN_BYTE_ARRAY, 2, FieldRepetitionType::REQUIRED, "f".to_string(), None,
+            None, None, None, None, LogicalType::FLOAT16(Float16Type::new()));
+        assert_eq!(SortOrder::Signed, sort_order(&element));
+        let mut invalid = element.clone();
+        invalid.type_length = Some(4);
+        assert_eq!((false, SortOrder::Undefined), (is_float16(&invalid), sort_order(&invalid)));
+        // -1.0 and 0.5 compare wrong as raw bytes
+        let a = comparable(&element, Value::Bytes(vec![0x00, 0xbc])).unwrap();
+        let b = comparable(&element, Value::Bytes(vec![0x00, 0x38])).unwrap();
+        assert_eq!(Value::Float(-1.0), a);
+        assert_eq!(Some(Ordering::Less), a.compare(&b, SortOrder::Signed));
+    }
+}
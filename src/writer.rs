@@ -0,0 +1,711 @@
+use std::io::{Write, Result, Error, ErrorKind};
+use std::mem;
+use byteorder::{LittleEndian, WriteBytesExt};
+use thrift::protocol::{TCompactOutputProtocol, TOutputProtocol};
+use parquet::*;
+use schema::{ColumnDescriptor, leaf_columns, validate_schema};
+use value::Value;
+use temporal::{Temporal, TemporalType, Unit, temporal_type};
+use column_writer::{ColumnWriter, ChunkBuffer};
+use reader::ColumnData;
+use record::{Row, Shredder};
+use properties::WriterProperties;
+use MAGIC;
+
+fn invalid_input<E: ToString>(e: E) -> Error {
+    Error::new(ErrorKind::InvalidInput, e.to_string())
+}
+
+fn thrift_error(what: &str, e: ::thrift::Error) -> Error {
+    Error::new(ErrorKind::Other, format!("Failed to serialize {}: {}", what, e))
+}
+
+/// Serialize a thrift struct with the compact protocol
+fn serialize<F>(what: &str, write: F) -> Result<Vec<u8>>
+    where F: FnOnce(&mut dyn TOutputProtocol) -> ::thrift::Result<()>
+{
+    let mut data = vec![];
+    {
+        let mut protocol = TCompactOutputProtocol::new(&mut data);
+        write(&mut protocol).and_then(|_| protocol.flush()).map_err(|e| thrift_error(what, e))?;
+    }
+    Ok(data)
+}
+
+/// Levels a column writer of `column` takes: those the column has
+fn levels<'a>(column: &ColumnDescriptor, def_levels: &'a [i16], rep_levels: &'a [i16]) -> (Option<&'a [i16]>, Option<&'a [i16]>) {
+    (if column.max_def_level > 0 { Some(def_levels) } else { None },
+     if column.max_rep_level > 0 { Some(rep_levels) } else { None })
+}
+
+/// Writes a parquet file: magic, row groups added one at a time, then page indexes
+/// and the footer on `close`. Pages are compressed with each column's codec.
+///
+/// The sink is only written to, never sought, so it can be a pipe or a socket.
+/// Offsets in metadata count bytes written, the sink must be at the start of the file.
+///
+/// Rows are encoded into a buffered row group as they are written, which is flushed to the sink
+/// once it reaches `max_row_group_rows` rows or `max_row_group_size` estimated bytes.
+///
+/// Batches are checked before they are written, an invalid one leaves the writer as it was. A column
+/// that fails to write a checked batch, such as a page that can not be compressed, leaves the columns
+/// of the row group with different numbers of rows: the writer is then poisoned and every later call fails.
+pub struct FileWriter<W: Write> {
+    sink: W,
+    /// Bytes written to the sink
+    offset: i64,
+    schema: Vec<SchemaElement>,
+    columns: Vec<ColumnDescriptor>,
+    props: WriterProperties,
+    /// Unit of INT64 timestamps of every column that are written as INT96
+    int96_units: Vec<Option<Unit>>,
+    /// Column writers of the buffered row group, empty if there is none
+    row_group: Vec<ColumnWriter<Value>>,
+    buffered_rows: usize,
+    shredder: Shredder,
+    row_groups: Vec<RowGroup>,
+    /// Page indexes of every column chunk of `row_groups`
+    page_indexes: Vec<Vec<(Option<ColumnIndex>, OffsetIndex)>>,
+    num_rows: i64,
+    /// Error of a failed column write that poisoned the writer
+    poisoned: Option<String>,
+}
+
+impl<W: Write> FileWriter<W> {
+    pub fn new(sink: W, schema: Vec<SchemaElement>) -> Result<FileWriter<W>> {
+        FileWriter::with_properties(sink, schema, WriterProperties::default())
+    }
+
+    pub fn with_properties(mut sink: W, mut schema: Vec<SchemaElement>, props: WriterProperties) -> Result<FileWriter<W>> {
+        validate_schema(&schema).map_err(|problems| invalid_input(problems.join("; ")))?;
+        let columns = leaf_columns(&schema).map_err(invalid_input)?;
+        if let Some(path) = props.column_paths().into_iter().find(|p| columns.iter().all(|c| c.path_string() != *p)) {
+            return Err(invalid_input(format!("Properties of unknown column '{}'", path)))
+        }
+
+        // INT64 timestamps written as INT96 lose their annotation, as old Hive and Impala expect
+        let mut int96_units = vec![None; columns.len()];
+        for (i, column) in columns.iter().enumerate() {
+            let element = &mut schema[column.schema_idx];
+            let temporal = temporal_type(element).map_err(invalid_input)?;
+            if let (Some(Type::INT64), Some(TemporalType::Timestamp { unit, .. })) = (element.type_, temporal) {
+                if props.int96_timestamps(&column.path_string()) {
+                    element.type_ = Some(Type::INT96);
+                    element.logical_type = None;
+                    element.converted_type = None;
+                    int96_units[i] = Some(unit);
+                }
+            }
+        }
+        let columns = leaf_columns(&schema).map_err(invalid_input)?;
+        let shredder = Shredder::new(&schema).map_err(invalid_input)?;
+
+        sink.write_all(MAGIC.as_bytes())?;
+        Ok(FileWriter { sink, offset: MAGIC.len() as i64, schema, columns, props, int96_units,
+            row_group: vec![], buffered_rows: 0, shredder, row_groups: vec![], page_indexes: vec![], num_rows: 0,
+            poisoned: None })
+    }
+
+    pub fn schema(&self) -> &[SchemaElement] {
+        &self.schema
+    }
+
+    pub fn columns(&self) -> &[ColumnDescriptor] {
+        &self.columns
+    }
+
+    /// Bytes written to the sink so far
+    pub fn bytes_written(&self) -> u64 {
+        self.offset as u64
+    }
+
+    pub fn properties(&self) -> &WriterProperties {
+        &self.props
+    }
+
+    /// Rows of the buffered row group
+    pub fn buffered_rows(&self) -> usize {
+        self.buffered_rows
+    }
+
+    /// Estimated encoded size of the buffered row group
+    pub fn buffered_size(&self) -> usize {
+        self.row_group.iter().map(|w| w.estimated_size()).sum()
+    }
+
+    /// Approximate bytes of memory held by the buffered row group
+    pub fn memory_size(&self) -> usize {
+        self.row_group.iter().map(|w| w.memory_size()).sum()
+    }
+
+    /// Write rows of a flat schema given values of every column in schema order, `None` being null,
+    /// to the buffered row group and flush it.
+    pub fn write_row_group(&mut self, columns: &[Vec<Option<Value>>]) -> Result<()> {
+        self.check_poisoned()?;
+        if let Some(nested) = self.columns.iter().find(|c| c.path.len() > 1 || c.max_rep_level > 0) {
+            return Err(Error::new(ErrorKind::Other, format!("Nested column '{}' needs levels, use ColumnWriter",
+                nested.path_string())))
+        }
+        if columns.len() != self.columns.len() {
+            return Err(invalid_input(format!("Expected {} columns but got {}", self.columns.len(), columns.len())))
+        }
+
+        let num_rows = columns.first().map_or(0, |c| c.len());
+        for (column, values) in self.columns.iter().zip(columns.iter()) {
+            if values.len() != num_rows {
+                return Err(invalid_input(format!("Column '{}' has {} rows but '{}' has {}",
+                    column.path_string(), values.len(), self.columns[0].path_string(), num_rows)))
+            }
+            if column.max_def_level == 0 && values.iter().any(|v| v.is_none()) {
+                return Err(invalid_input(format!("Null value in required column '{}'", column.path_string())))
+            }
+        }
+
+        let data = columns.iter().map(|values| ColumnData {
+            def_levels: values.iter().map(|v| v.is_some() as i16).collect(),
+            rep_levels: vec![0; values.len()],
+            values: values.iter().filter_map(|v| v.clone()).collect(),
+        }).collect();
+        // all rows are checked before any is written, batches only split them
+        let data = self.check_column_data(data)?;
+        let mut value_starts = vec![0; data.len()];
+        let mut start = 0;
+        loop {
+            let end = start + self.batch_rows().min(num_rows - start);
+            let batch = data.iter().zip(value_starts.iter_mut()).map(|(data, value_start)| {
+                let def_levels = data.def_levels[start..end].to_vec();
+                let value_end = *value_start + def_levels.iter().filter(|&&d| d > 0).count();
+                let values = data.values[*value_start..value_end].to_vec();
+                *value_start = value_end;
+                ColumnData { def_levels, rep_levels: data.rep_levels[start..end].to_vec(), values }
+            }).collect();
+            self.write_checked(batch, end - start)?;
+            start = end;
+            if start >= num_rows {
+                break
+            }
+        }
+        self.flush()
+    }
+
+    /// Write records, shredded into levels and values of every leaf column, to the buffered row group.
+    /// Records before an invalid one are written.
+    pub fn write_records(&mut self, rows: &[Row]) -> Result<()> {
+        self.check_poisoned()?;
+        let mut start = 0;
+        while start < rows.len() {
+            let end = start + self.batch_rows().min(rows.len() - start);
+            let res = rows[start..end].iter().try_for_each(|row| self.shredder.write_row(row));
+            let num_rows = self.shredder.num_rows();
+            let data = self.shredder.take();
+            self.write_column_data(data, num_rows)?;
+            res.map_err(invalid_input)?;
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Rows to buffer before the row group size is checked again: half of those that fit
+    /// the remaining budget at the average size of buffered rows
+    fn batch_rows(&self) -> usize {
+        let rows_left = self.props.max_row_group_rows().saturating_sub(self.buffered_rows).max(1);
+        if self.buffered_rows == 0 {
+            return 1
+        }
+        let size = self.buffered_size();
+        let row_size = (size / self.buffered_rows).max(1);
+        (self.props.max_row_group_size().saturating_sub(size) / row_size / 2).max(1).min(rows_left)
+    }
+
+    /// Add levels and values of every leaf column to the buffered row group, flushing it when it is full.
+    /// The data of all columns is checked before any is written, an invalid batch leaves the row group as it was.
+    fn write_column_data(&mut self, data: Vec<ColumnData>, num_rows: usize) -> Result<()> {
+        let data = self.check_column_data(data)?;
+        self.write_checked(data, num_rows)
+    }
+
+    /// Levels and values of every leaf column with timestamps written as INT96 converted, checked against
+    /// the column writers of the buffered row group. Writers created for the check are dropped if it fails.
+    fn check_column_data(&mut self, data: Vec<ColumnData>) -> Result<Vec<ColumnData>> {
+        let mut checked = Vec::with_capacity(data.len());
+        for (i, (column, mut data)) in self.columns.iter().zip(data).enumerate() {
+            if let Some(unit) = self.int96_units[i] {
+                for value in data.values.iter_mut() {
+                    if let Value::Int64(v) = *value {
+                        *value = Temporal::Timestamp { value: v, unit, adjusted_to_utc: true }.to_int96().
+                            map_err(|e| invalid_input(format!("Column '{}': {}", column.path_string(), e)))?;
+                    }
+                }
+            }
+            checked.push(data);
+        }
+
+        let new_row_group = self.start_row_group()?;
+        for ((writer, column), data) in self.row_group.iter().zip(self.columns.iter()).zip(checked.iter()) {
+            let (def_levels, rep_levels) = levels(column, &data.def_levels, &data.rep_levels);
+            if let Err(e) = writer.check_batch(&data.values, def_levels, rep_levels) {
+                if new_row_group {
+                    self.row_group.clear();
+                }
+                return Err(e)
+            }
+        }
+        Ok(checked)
+    }
+
+    /// Create the column writers of a new row group if none is buffered, returns whether it did
+    fn start_row_group(&mut self) -> Result<bool> {
+        if !self.row_group.is_empty() {
+            return Ok(false)
+        }
+        let mut writers = Vec::with_capacity(self.columns.len());
+        for column in self.columns.iter() {
+            let props = self.props.column_properties(&column.path_string());
+            writers.push(ColumnWriter::new(column.clone(), &self.schema[column.schema_idx], props)?);
+        }
+        self.row_group = writers;
+        Ok(true)
+    }
+
+    /// Add data checked by `check_column_data` to the buffered row group, flushing it when it is full
+    fn write_checked(&mut self, data: Vec<ColumnData>, num_rows: usize) -> Result<()> {
+        self.start_row_group()?;
+        let columns = &self.columns;
+        let written = self.row_group.iter_mut().zip(columns.iter()).zip(data.iter()).try_for_each(|((writer, column), data)| {
+            let (def_levels, rep_levels) = levels(column, &data.def_levels, &data.rep_levels);
+            writer.write_batch(&data.values, def_levels, rep_levels).map(|_| ())
+        });
+        if let Err(e) = written {
+            self.poisoned = Some(e.to_string());
+            return Err(e)
+        }
+
+        self.buffered_rows += num_rows;
+        if self.buffered_rows >= self.props.max_row_group_rows() || self.buffered_size() >= self.props.max_row_group_size() {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Write the buffered row group, if any. The row group stays buffered if its chunks can not be finished,
+    /// a failed write to the sink leaves the file incomplete.
+    pub fn flush(&mut self) -> Result<()> {
+        self.check_poisoned()?;
+        if self.row_group.is_empty() {
+            return Ok(())
+        }
+        let num_rows = self.row_group[0].num_rows();
+        if let Some(writer) = self.row_group.iter().find(|w| w.num_rows() != num_rows) {
+            return Err(Error::new(ErrorKind::Other, format!("Column '{}' has {} rows but '{}' has {}",
+                writer.column().path_string(), writer.num_rows(), self.columns[0].path_string(), num_rows)))
+        }
+        for writer in self.row_group.iter_mut() {
+            writer.finish()?;
+        }
+
+        self.buffered_rows = 0;
+        // closing finished writers does not fail
+        let chunks = mem::take(&mut self.row_group).into_iter().
+            map(|writer| writer.close()).
+            collect::<Result<Vec<_>>>()?;
+        self.write_chunks(chunks)
+    }
+
+    /// Write a row group of column chunks encoded by `ColumnWriter`s, one for every leaf column in schema order,
+    /// after the buffered row group
+    pub fn append_row_group(&mut self, chunks: Vec<ChunkBuffer>) -> Result<()> {
+        self.check_poisoned()?;
+        self.flush()?;
+        self.write_chunks(chunks)
+    }
+
+    fn check_poisoned(&self) -> Result<()> {
+        match self.poisoned {
+            Some(ref e) => Err(Error::new(ErrorKind::Other, format!("Writer can not be used after a failed column write: {}", e))),
+            None => Ok(()),
+        }
+    }
+
+    fn write_chunks(&mut self, chunks: Vec<ChunkBuffer>) -> Result<()> {
+        if chunks.len() != self.columns.len() {
+            return Err(invalid_input(format!("Expected {} columns but got {}", self.columns.len(), chunks.len())))
+        }
+        let num_rows = chunks.first().map_or(0, |c| c.num_rows());
+        for (column, chunk) in self.columns.iter().zip(chunks.iter()) {
+            if chunk.meta_data().path_in_schema != column.path {
+                return Err(invalid_input(format!("Expected column '{}' but got '{}'",
+                    column.path_string(), chunk.meta_data().path_in_schema.join("."))))
+            }
+            if chunk.num_rows() != num_rows {
+                return Err(invalid_input(format!("Column '{}' has {} rows but '{}' has {}",
+                    column.path_string(), chunk.num_rows(), self.columns[0].path_string(), num_rows)))
+            }
+        }
+
+        let mut columns = Vec::with_capacity(chunks.len());
+        let mut page_indexes = Vec::with_capacity(chunks.len());
+        let mut total_byte_size = 0;
+        for chunk in chunks {
+            let offset = self.offset;
+            self.sink.write_all(&chunk.data)?;
+            self.offset += chunk.data.len() as i64;
+            total_byte_size += chunk.meta_data().total_uncompressed_size;
+            columns.push(chunk.column_chunk(offset));
+            page_indexes.push((chunk.column_index().cloned(), chunk.offset_index(offset)));
+        }
+
+        self.row_groups.push(RowGroup::new(columns, total_byte_size, num_rows, None));
+        self.page_indexes.push(page_indexes);
+        self.num_rows += num_rows;
+        Ok(())
+    }
+
+    /// Write the buffered row group, page indexes and the footer and return the sink
+    pub fn close(mut self) -> Result<W> {
+        self.flush()?;
+        let mut offset = self.offset;
+        // column indexes of all chunks, then their offset indexes
+        for (row_group, indexes) in self.row_groups.iter_mut().zip(self.page_indexes.iter()) {
+            for (chunk, (column_index, _)) in row_group.columns.iter_mut().zip(indexes.iter()) {
+                if let Some(ref index) = *column_index {
+                    let data = serialize("ColumnIndex", |p| index.write_to_out_protocol(p))?;
+                    self.sink.write_all(&data)?;
+                    chunk.column_index_offset = Some(offset);
+                    chunk.column_index_length = Some(data.len() as i32);
+                    offset += data.len() as i64;
+                }
+            }
+        }
+        for (row_group, indexes) in self.row_groups.iter_mut().zip(self.page_indexes.iter()) {
+            for (chunk, (_, index)) in row_group.columns.iter_mut().zip(indexes.iter()) {
+                let data = serialize("OffsetIndex", |p| index.write_to_out_protocol(p))?;
+                self.sink.write_all(&data)?;
+                chunk.offset_index_offset = Some(offset);
+                chunk.offset_index_length = Some(data.len() as i32);
+                offset += data.len() as i64;
+            }
+        }
+
+        let key_value_metadata = if self.props.key_value_metadata().is_empty() {
+            None
+        } else {
+            Some(self.props.key_value_metadata().to_vec())
+        };
+        // statistics min/max values are only defined for readers with the column orders
+        let column_orders = vec![ColumnOrder::TYPEORDER(TypeDefinedOrder::new()); self.columns.len()];
+        let meta = FileMetaData::new(self.props.version(), self.schema, self.num_rows, self.row_groups, key_value_metadata,
+            self.props.created_by().to_string(), column_orders);
+        let data = serialize("FileMetaData", |p| meta.write_to_out_protocol(p))?;
+        self.sink.write_all(&data)?;
+
+        self.sink.write_u32::<LittleEndian>(data.len() as u32)?;
+        self.sink.write_all(MAGIC.as_bytes())?;
+        self.sink.flush()?;
+        Ok(self.sink)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use schema::Schema;
+    use reader::{read_metadata, read_column_chunk};
+    use record::{RowIter, Field, read_nested_column};
+    use aggregate::aggregate;
+    use index::{read_column_index, read_offset_index, page_rows, PageBounds};
+    use column_writer::{ColumnProperties, WriterVersion};
+    use temporal::nanos_to_int96;
+
+    #[test]
+    fn write_and_read_back() {
+        let schema = Schema::message("m").
+            required_int64("id").
+            optional_string("name").
+            optional_boolean("flag").
+            build().unwrap();
+        let mut writer = FileWriter::new(Cursor::new(vec![]), schema).unwrap();
+        writer.write_row_group(&[
+            vec![Some(Value::Int64(1)), Some(Value::Int64(2)), Some(Value::Int64(3))],
+            vec![Some(Value::Bytes(b"a".to_vec())), None, Some(Value::Bytes(b"c".to_vec()))],
+            vec![None, Some(Value::Boolean(true)), Some(Value::Boolean(false))],
+        ]).unwrap();
+        writer.write_row_group(&[vec![Some(Value::Int64(4))], vec![None], vec![None]]).unwrap();
+        let mut file = writer.close().unwrap();
+
+        assert_eq!(b"PAR1", &file.get_ref()[..4]);
+        let meta = read_metadata(&mut file).unwrap();
+        assert_eq!((4, 2), (meta.num_rows, meta.row_groups.len()));
+        assert_eq!(Some(vec![ColumnOrder::TYPEORDER(TypeDefinedOrder::new()); 3]), meta.column_orders);
+
+        let columns = leaf_columns(&meta.schema).unwrap();
+        let data = read_column_chunk(&mut file, &meta.row_groups[0].columns[1], &columns[1]).unwrap();
+        assert_eq!(vec![1, 0, 1], data.def_levels);
+        assert_eq!(vec![Value::Bytes(b"a".to_vec()), Value::Bytes(b"c".to_vec())], data.values);
+
+        let rows: Vec<_> = RowIter::new(&mut file, &meta).collect::<Result<_>>().unwrap();
+        assert_eq!(4, rows.len());
+        assert_eq!(Some(&Field::Str("c".to_string())), rows[2].get("name"));
+        assert_eq!(Some(&Field::Null), rows[3].get("flag"));
+
+        // answered from statistics alone
+        let res = aggregate(&mut file, &meta, "name", |_| true).unwrap();
+        assert!(res.scanned_row_groups.is_empty());
+        assert_eq!((2, 2), (res.count, res.null_count));
+        assert_eq!((Some(Value::Bytes(b"a".to_vec())), Some(Value::Bytes(b"c".to_vec()))), (res.min, res.max));
+    }
+
+    #[test]
+    fn page_indexes() {
+        let schema = Schema::message("m").required_int64("id").optional_string("name").build().unwrap();
+        let mut writer = FileWriter::new(Cursor::new(vec![]), schema).unwrap();
+        let columns = writer.columns().to_vec();
+        let props = ColumnProperties { page_size: 1, dictionary_enabled: false, ..ColumnProperties::default() };
+        let mut ids = ColumnWriter::<i64>::new(columns[0].clone(), &writer.schema()[1], props.clone()).unwrap();
+        ids.write_batch(&[10, 20, 30], None, None).unwrap();
+        let mut names = ColumnWriter::<Vec<u8>>::new(columns[1].clone(), &writer.schema()[2], props).unwrap();
+        names.write_batch(&[], Some(&[0, 0, 0]), None).unwrap();
+        writer.append_row_group(vec![ids.close().unwrap(), names.close().unwrap()]).unwrap();
+        writer.write_row_group(&[vec![Some(Value::Int64(40))], vec![None]]).unwrap();
+        let mut file = writer.close().unwrap();
+
+        let meta = read_metadata(&mut file).unwrap();
+        let chunk = &meta.row_groups[0].columns[0];
+        let offsets = read_offset_index(&mut file, chunk).unwrap().unwrap();
+        assert_eq!(3, offsets.page_locations.len());
+        assert_eq!(chunk.meta_data.as_ref().unwrap().data_page_offset, offsets.page_locations[0].offset);
+        assert_eq!(2..3, page_rows(&offsets, 2, meta.row_groups[0].num_rows));
+
+        let index = read_column_index(&mut file, chunk).unwrap().unwrap();
+        let bounds = PageBounds::new(&index, &meta.schema[1]).unwrap();
+        assert_eq!(BoundaryOrder::ASCENDING, bounds.boundary_order());
+        assert_eq!(vec![1], bounds.find_eq(&Value::Int64(20)));
+
+        let names = read_column_index(&mut file, &meta.row_groups[1].columns[1]).unwrap().unwrap();
+        assert_eq!((vec![true], Some(vec![1])), (names.null_pages, names.null_counts));
+    }
+
+    #[test]
+    fn writer_properties() {
+        let schema = Schema::message("m").
+            required_int64("id").
+            optional_int64("ts").logical(LogicalType::TIMESTAMP(TimestampType::new(true, TimeUnit::MILLIS(MilliSeconds::new())))).
+            optional_string("name").
+            build().unwrap();
+        let props = WriterProperties::builder().
+            created_by("ingest 1.0").
+            key_value("origin", "test").
+            max_row_group_rows(2).
+            defaults(|c| c.dictionary_enabled(false)).
+            column("ts", |c| c.int96_timestamps(true)).
+            column("name", |c| c.compression(CompressionCodec::SNAPPY).statistics_enabled(false)).
+            build();
+        let mut writer = FileWriter::with_properties(Cursor::new(vec![]), schema, props).unwrap();
+        writer.write_row_group(&[
+            (1..6).map(|i| Some(Value::Int64(i))).collect(),
+            vec![Some(Value::Int64(1000)), None, Some(Value::Int64(-1)), None, None],
+            vec![Some(Value::Bytes(b"a".to_vec())), None, None, None, Some(Value::Bytes(b"e".to_vec()))],
+        ]).unwrap();
+        let mut file = writer.close().unwrap();
+
+        let meta = read_metadata(&mut file).unwrap();
+        assert_eq!(vec![2, 2, 1], meta.row_groups.iter().map(|rg| rg.num_rows).collect::<Vec<_>>());
+        assert_eq!(Some("ingest 1.0".to_string()), meta.created_by);
+        assert_eq!(Some(vec![KeyValue::new("origin".to_string(), "test".to_string())]), meta.key_value_metadata);
+        assert_eq!((Some(Type::INT96), None, None), (meta.schema[2].type_, meta.schema[2].logical_type.clone(), meta.schema[2].converted_type));
+
+        let columns = leaf_columns(&meta.schema).unwrap();
+        let ts = read_column_chunk(&mut file, &meta.row_groups[1].columns[1], &columns[1]).unwrap();
+        assert_eq!(vec![Value::Int96(nanos_to_int96(-1_000_000))], ts.values);
+        let name = meta.row_groups[0].columns[2].meta_data.as_ref().unwrap();
+        assert_eq!((CompressionCodec::SNAPPY, None), (name.codec, name.statistics.clone()));
+        let id = meta.row_groups[0].columns[0].meta_data.as_ref().unwrap();
+        assert_eq!((CompressionCodec::UNCOMPRESSED, vec![Encoding::PLAIN]), (id.codec, id.encodings.clone()));
+
+        // 8 bytes of every id
+        let props = WriterProperties::builder().max_row_group_size(20).build();
+        let schema = Schema::message("m").required_int64("id").build().unwrap();
+        let mut writer = FileWriter::with_properties(Cursor::new(vec![]), schema.clone(), props).unwrap();
+        writer.write_row_group(&[(1..6).map(|i| Some(Value::Int64(i))).collect()]).unwrap();
+        let meta = read_metadata(&mut writer.close().unwrap()).unwrap();
+        assert_eq!(vec![3, 2], meta.row_groups.iter().map(|rg| rg.num_rows).collect::<Vec<_>>());
+
+        let props = WriterProperties::builder().column("name", |c| c.page_size(10)).build();
+        assert!(FileWriter::with_properties(Cursor::new(vec![]), schema, props).is_err());
+    }
+
+    #[test]
+    fn write_nested_records() {
+        let schema = Schema::message("m").
+            required_int64("id").
+            optional_list("tags", |e| e.required_string("element")).
+            optional_int64("ts").logical(LogicalType::TIMESTAMP(TimestampType::new(true, TimeUnit::MICROS(MicroSeconds::new())))).
+            build().unwrap();
+        let props = WriterProperties::builder().
+            max_row_group_rows(2).
+            defaults(|c| c.int96_timestamps(true).writer_version(WriterVersion::V2)).
+            build();
+        let mut writer = FileWriter::with_properties(Cursor::new(vec![]), schema, props).unwrap();
+        let ts = Field::Temporal(Temporal::Timestamp { value: 1_000, unit: Unit::Micros, adjusted_to_utc: true });
+        let rows: Vec<Row> = (0..3).map(|i| Row { fields: vec![
+            ("id".to_string(), Field::Int64(i)),
+            ("tags".to_string(), Field::List((0..i).map(|t| Field::Str(t.to_string())).collect())),
+            ("ts".to_string(), if i == 1 { ts.clone() } else { Field::Null }),
+        ]}).collect();
+        writer.write_records(&rows).unwrap();
+        let bad = Row { fields: vec![("id".to_string(), Field::Null)] };
+        assert_eq!(ErrorKind::InvalidInput, writer.write_records(&[bad]).unwrap_err().kind());
+        let mut file = writer.close().unwrap();
+
+        let meta = read_metadata(&mut file).unwrap();
+        assert_eq!(vec![2, 1], meta.row_groups.iter().map(|rg| rg.num_rows).collect::<Vec<_>>());
+        assert_eq!(2, meta.version);
+        let read: Vec<Row> = RowIter::new(&mut file, &meta).collect::<Result<_>>().unwrap();
+        assert_eq!((&rows[0], &rows[2]), (&read[0], &read[2]));
+        assert_eq!(rows[1].get("tags"), read[1].get("tags"));
+        // lists of the first row group in columnar form: [], ["0"]
+        let tags = read_nested_column(&mut file, &meta, 0, "tags.list.element").unwrap();
+        assert_eq!((vec![vec![0, 0, 1]], vec![vec![true, true]]), (tags.offsets, tags.validity));
+        assert_eq!(vec![Some(Field::Str("0".to_string()))], tags.values);
+        // INT96 timestamps read back in nanoseconds
+        let nanos = Field::Temporal(Temporal::Timestamp { value: 1_000_000, unit: Unit::Nanos, adjusted_to_utc: true });
+        assert_eq!(Some(&nanos), read[1].get("ts"));
+    }
+
+    #[test]
+    fn bounded_row_groups() {
+        let schema = Schema::message("m").required_int64("id").optional_string("name").build().unwrap();
+        let props = WriterProperties::builder().
+            max_row_group_size(16 * 1024).
+            defaults(|c| c.dictionary_enabled(false).page_size(1024)).
+            build();
+        let mut writer = FileWriter::with_properties(vec![], schema, props).unwrap();
+        let rows: Vec<Row> = (0..10_000).map(|i| Row { fields: vec![
+            ("id".to_string(), Field::Int64(i)),
+            ("name".to_string(), if i % 3 == 0 { Field::Null } else { Field::Str(format!("name {}", i)) }),
+        ]}).collect();
+
+        let mut max_memory = 0;
+        for batch in rows.chunks(1000) {
+            writer.write_records(batch).unwrap();
+            assert!(writer.buffered_size() < 16 * 1024);
+            max_memory = max_memory.max(writer.memory_size());
+        }
+        // memory is bounded by the row group budget, not the rows written
+        assert!(max_memory < 4 * 16 * 1024);
+        assert!(writer.buffered_rows() > 0);
+        writer.flush().unwrap();
+        assert_eq!((0, 0), (writer.buffered_rows(), writer.memory_size()));
+
+        let mut file = Cursor::new(writer.close().unwrap());
+        let meta = read_metadata(&mut file).unwrap();
+        assert!(meta.row_groups.len() > 5);
+        assert_eq!(10_000, meta.row_groups.iter().map(|rg| rg.num_rows).sum::<i64>());
+        // row groups are closed close to the budget
+        for rg in &meta.row_groups[..meta.row_groups.len() - 1] {
+            let size: i64 = rg.columns.iter().map(|c| c.meta_data.as_ref().unwrap().total_compressed_size).sum();
+            assert!(size > 8 * 1024 && size < 24 * 1024, "row group of {} bytes", size);
+        }
+        let read: Vec<Row> = RowIter::new(&mut file, &meta).collect::<Result<_>>().unwrap();
+        assert_eq!(rows, read);
+    }
+
+    #[test]
+    fn invalid_batches_keep_buffered_rows() {
+        let schema = Schema::message("m").
+            required_int64("id").
+            optional_int64("ts").logical(LogicalType::TIMESTAMP(TimestampType::new(true, TimeUnit::MICROS(MicroSeconds::new())))).
+            build().unwrap();
+        let props = WriterProperties::builder().column("ts", |c| c.int96_timestamps(true)).build();
+        let mut writer = FileWriter::with_properties(vec![], schema, props).unwrap();
+        let rows: Vec<Row> = (0..3).map(|i| Row { fields: vec![
+            ("id".to_string(), Field::Int64(i)),
+            ("ts".to_string(), Field::Null),
+        ]}).collect();
+        writer.write_records(&rows).unwrap();
+
+        // wrong type of the first column, and a timestamp of the second that overflows INT96
+        assert!(writer.write_row_group(&[vec![Some(Value::Int32(1))], vec![None]]).is_err());
+        assert!(writer.write_row_group(&[vec![Some(Value::Int64(1))], vec![Some(Value::Int64(i64::MAX))]]).is_err());
+        assert_eq!(3, writer.buffered_rows());
+        writer.write_records(&rows[..1]).unwrap();
+        writer.flush().unwrap();
+
+        // rows are checked before any of them is written: the second one is invalid
+        assert!(writer.write_row_group(&[vec![Some(Value::Int64(1)), Some(Value::Int64(2)), Some(Value::Int64(3))],
+            vec![None, Some(Value::Int32(2)), None]]).is_err());
+        assert!(writer.write_row_group(&[vec![Some(Value::Int64(1)), Some(Value::Int64(2))],
+            vec![None, Some(Value::Int64(i64::MAX))]]).is_err());
+        assert_eq!(0, writer.buffered_rows());
+
+        let mut file = Cursor::new(writer.close().unwrap());
+        let meta = read_metadata(&mut file).unwrap();
+        assert_eq!((4, 1), (meta.num_rows, meta.row_groups.len()));
+        let read: Vec<Row> = RowIter::new(&mut file, &meta).collect::<Result<_>>().unwrap();
+        assert_eq!(&rows[..], &read[..3]);
+    }
+
+    #[test]
+    fn non_seekable_sink() {
+        let schema = Schema::message("m").required_int64("id").optional_string("name").build().unwrap();
+        fn write<W: Write>(sink: W, schema: Vec<SchemaElement>) -> FileWriter<W> {
+            let mut writer = FileWriter::new(sink, schema).unwrap();
+            writer.write_row_group(&[vec![Some(Value::Int64(1))], vec![Some(Value::Bytes(b"a".to_vec()))]]).unwrap();
+            writer.write_row_group(&[vec![Some(Value::Int64(2))], vec![None]]).unwrap();
+            writer
+        }
+
+        // a Vec is Write but not Seek
+        let writer = write(vec![], schema.clone());
+        let written = writer.bytes_written();
+        let data = writer.close().unwrap();
+        assert!(written > 4 && written < data.len() as u64);
+        assert_eq!(data, write(Cursor::new(vec![]), schema).close().unwrap().into_inner());
+
+        let mut file = Cursor::new(data);
+        let meta = read_metadata(&mut file).unwrap();
+        let rows: Vec<_> = RowIter::new(&mut file, &meta).collect::<Result<_>>().unwrap();
+        assert_eq!(Some(&Field::Int64(2)), rows[1].get("id"));
+        let chunk = &meta.row_groups[1].columns[1];
+        assert!(read_offset_index(&mut file, chunk).unwrap().is_some());
+    }
+
+    #[test]
+    fn invalid_input() {
+        let schema = Schema::message("m").required_int32("a").build().unwrap();
+        let mut writer = FileWriter::new(Cursor::new(vec![]), schema).unwrap();
+        assert!(writer.write_row_group(&[vec![None]]).is_err());
+        assert!(writer.write_row_group(&[vec![Some(Value::Int64(1))]]).is_err());
+        assert!(writer.write_row_group(&[]).is_err());
+
+        let nested = Schema::message("m").optional_group("g", |g| g.required_int32("a")).build().unwrap();
+        let mut writer = FileWriter::new(Cursor::new(vec![]), nested).unwrap();
+        assert!(writer.write_row_group(&[vec![Some(Value::Int32(1))]]).is_err());
+    }
+
+    #[test]
+    fn append_column_chunks() {
+        let schema = Schema::message("m").
+            required_int64("id").
+            optional_list("tags", |e| e.required_string("element")).
+            build().unwrap();
+        let mut writer = FileWriter::new(Cursor::new(vec![]), schema).unwrap();
+        let columns = writer.columns().to_vec();
+        let element = |i: usize| writer.schema()[columns[i].schema_idx].clone();
+
+        let mut ids = ColumnWriter::<i64>::new(columns[0].clone(), &element(0), ColumnProperties::default()).unwrap();
+        ids.write_batch(&[1, 2], None, None).unwrap();
+        let mut tags = ColumnWriter::<Vec<u8>>::new(columns[1].clone(), &element(1), ColumnProperties::default()).unwrap();
+        tags.write_batch(&[b"x".to_vec(), b"y".to_vec()], Some(&[2, 2, 0]), Some(&[0, 1, 0])).unwrap();
+        let (ids, tags) = (ids.close().unwrap(), tags.close().unwrap());
+
+        assert!(writer.append_row_group(vec![tags.clone(), ids.clone()]).is_err());
+        writer.append_row_group(vec![ids, tags]).unwrap();
+        let mut file = writer.close().unwrap();
+
+        let meta = read_metadata(&mut file).unwrap();
+        let rows: Vec<_> = RowIter::new(&mut file, &meta).collect::<Result<_>>().unwrap();
+        assert_eq!(Some(&Field::List(vec![Field::Str("x".to_string()), Field::Str("y".to_string())])), rows[0].get("tags"));
+        assert_eq!(Some(&Field::Null), rows[1].get("tags"));
+    }
+}